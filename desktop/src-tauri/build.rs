@@ -1,3 +1,21 @@
+use std::process::Command;
+
 fn main() {
+    // Surfaced by the get_app_version command
+    println!(
+        "cargo:rustc-env=RELATE_BUILD_DATE={}",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=RELATE_GIT_COMMIT={}", commit.trim());
+    }
+
     tauri_build::build()
 }
@@ -1,12 +1,32 @@
+use crate::commands::auth::touch_account_last_used;
+use crate::commands::oidc::token_expiry;
 use crate::commands::AppState;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use tauri::ipc::Channel;
 use tauri::State;
+use uuid::Uuid;
+
+/// Number of recent requests kept for support diagnostics
+const REQUEST_LOG_CAPACITY: usize = 50;
+/// Treat tokens this close to `exp` as already expired to allow for clock skew
+const TOKEN_EXPIRY_LEEWAY_SECS: i64 = 30;
+const MAX_BATCH_CONCURRENCY: u8 = 16;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ApiError {
     #[error("Not configured: {0}")]
     NotConfigured(String),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
     #[error("Request failed: {0}")]
     RequestFailed(String),
+    #[error("Response exceeds the maximum size of {max_bytes} bytes")]
+    ResponseTooLarge { max_bytes: u64 },
+    #[error("Rate limited by server{}", retry_after_suffix(*.retry_after_secs))]
+    RateLimited { retry_after_secs: Option<u64> },
+    #[error("Credentials have expired, sign in again")]
+    TokenExpired,
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -20,62 +40,305 @@ impl serde::Serialize for ApiError {
     }
 }
 
-fn get_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
+fn retry_after_suffix(retry_after_secs: Option<u64>) -> String {
+    retry_after_secs
+        .map(|secs| format!(", retry after {secs}s"))
         .unwrap_or_default()
 }
 
-async fn make_request(
+/// Request payload sent by `make_request`
+enum RequestBody {
+    /// Raw body with its Content-Type (JSON unless specified)
+    Text {
+        body: String,
+        content_type: Option<String>,
+    },
+    /// Fields encoded as `application/x-www-form-urlencoded`
+    Form(HashMap<String, String>),
+    /// `multipart/form-data`; reqwest generates the boundary
+    Multipart(reqwest::multipart::Form),
+}
+
+impl RequestBody {
+    fn json(body: String) -> Self {
+        RequestBody::Text {
+            body,
+            content_type: None,
+        }
+    }
+
+    /// Combine the body-related command arguments; a raw body and form fields are exclusive
+    fn from_args(
+        body: Option<String>,
+        content_type: Option<String>,
+        body_form: Option<HashMap<String, String>>,
+    ) -> Result<Option<Self>, ApiError> {
+        match (body, body_form) {
+            (Some(_), Some(_)) => Err(ApiError::InvalidRequest(
+                "body and body_form cannot both be set".to_string(),
+            )),
+            (Some(body), None) => Ok(Some(RequestBody::Text { body, content_type })),
+            (None, Some(fields)) => Ok(Some(RequestBody::Form(fields))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// A piece of a streamed response; the last chunk has `done` set or carries an `error`
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct StreamChunk {
+    pub data: Vec<u8>,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct BatchRequest {
+    pub id: String,
+    pub method: String,
+    pub endpoint: String,
+    pub body: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct BatchResult {
+    pub id: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+}
+
+/// How a list endpoint pages its results
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PaginationStyle {
+    /// Response carries the next cursor in `cursor_field`, sent back as `cursor_param`
+    Cursor {
+        cursor_field: String,
+        cursor_param: String,
+    },
+    /// Pages are numbered from 1 and requested `page_size` items at a time
+    Offset {
+        page_param: String,
+        page_size_param: String,
+        page_size: u32,
+    },
+}
+
+/// A recent API request, kept so users can quote its ID when reporting problems
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct RequestRecord {
+    pub request_id: String,
+    pub endpoint: String,
+    pub method: String,
+    pub timestamp: String,
+    pub status_code: Option<u16>,
+}
+
+/// Most recent `X-RateLimit-*` values reported by the server
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct RateLimitInfo {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset: Option<u64>,
+}
+
+/// Whether the backend has credentials to make API calls, without exposing them
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct AppStateSummary {
+    pub has_server_url: bool,
+    pub has_api_key: bool,
+    /// Hostname of the server URL only; no credentials, port or path
+    pub server_url_host: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerCapabilities {
+    pub has_search: bool,
+    pub has_smtp: bool,
+    pub has_push_notifications: bool,
+    pub max_attachment_size_mb: Option<u32>,
+    pub api_version: String,
+}
+
+pub(crate) fn get_client(state: &AppState) -> Result<reqwest::Client, ApiError> {
+    state.http_client().map_err(ApiError::Internal)
+}
+
+fn record_request(state: &AppState, record: RequestRecord) -> Result<(), ApiError> {
+    let mut log = state
+        .request_log
+        .write()
+        .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?;
+    if log.len() >= REQUEST_LOG_CAPACITY {
+        log.pop_front();
+    }
+    log.push_back(record);
+    Ok(())
+}
+
+/// Whether `jwt` is past its `exp` claim. Opaque (non-JWT) keys and tokens without
+/// `exp` never count as expired.
+fn is_token_expired(jwt: &str) -> bool {
+    match token_expiry(jwt) {
+        Ok(Some(exp)) => exp - TOKEN_EXPIRY_LEEWAY_SECS <= chrono::Utc::now().timestamp(),
+        _ => false,
+    }
+}
+
+fn header_u64(headers: &reqwest::header::HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Parse `Retry-After` as either delay-seconds or an HTTP-date
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds();
+    Some(u64::try_from(secs).unwrap_or(0))
+}
+
+fn parse_rate_limit_info(headers: &reqwest::header::HeaderMap) -> Option<RateLimitInfo> {
+    let info = RateLimitInfo {
+        limit: header_u64(headers, "x-ratelimit-limit"),
+        remaining: header_u64(headers, "x-ratelimit-remaining"),
+        reset: header_u64(headers, "x-ratelimit-reset"),
+    };
+
+    (info.limit.is_some() || info.remaining.is_some() || info.reset.is_some()).then_some(info)
+}
+
+/// Send an authenticated API request, returning the successful response and its request ID
+async fn send_request(
     state: &State<'_, AppState>,
     method: reqwest::Method,
     endpoint: &str,
-    body: Option<String>,
-) -> Result<String, ApiError> {
+    body: Option<RequestBody>,
+) -> Result<(reqwest::Response, String), ApiError> {
     let server_url = state
         .server_url
         .read()
-        .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?
+        .await
         .clone()
         .ok_or_else(|| ApiError::NotConfigured("Server URL not set".to_string()))?;
 
     let api_key = state
         .api_key
         .read()
-        .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?
+        .await
         .clone()
         .ok_or_else(|| ApiError::NotConfigured("API key not set".to_string()))?;
 
+    // Fail fast rather than wait for the server to reject an expired token
+    if is_token_expired(&api_key) {
+        return Err(ApiError::TokenExpired);
+    }
+
+    let api_version = state.api_version.read().await.clone();
+
     let url = format!("{server_url}/api{endpoint}");
-    let client = get_client();
+    let client = get_client(state)?;
+
+    let request_id = Uuid::new_v4().to_string();
+    let mut record = RequestRecord {
+        request_id: request_id.clone(),
+        endpoint: endpoint.to_string(),
+        method: method.to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        status_code: None,
+    };
+
+    // Servers commonly ignore (or reject) bodies on these, but leave that to the server
+    if body.is_some() && matches!(method, reqwest::Method::GET | reqwest::Method::HEAD) {
+        eprintln!("Warning: sending a request body with {method} {endpoint}");
+    }
 
     let mut request = client
         .request(method, &url)
         .header("X-Api-Key", &api_key)
-        .header("Content-Type", "application/json");
+        .header("X-Request-ID", &request_id);
+    if let Some(version) = &api_version {
+        request = request.header("API-Version", version);
+    }
 
-    if let Some(body) = body {
-        request = request.body(body);
+    // Only send a Content-Type when there is a body to describe
+    match body {
+        Some(RequestBody::Text { body, content_type }) => {
+            let content_type = content_type.unwrap_or_else(|| "application/json".to_string());
+            request = request.header("Content-Type", content_type).body(body);
+        }
+        // reqwest sets the form Content-Type itself
+        Some(RequestBody::Form(fields)) => request = request.form(&fields),
+        Some(RequestBody::Multipart(form)) => request = request.multipart(form),
+        None => {}
     }
 
-    let response = request
-        .send()
-        .await
-        .map_err(|e| ApiError::RequestFailed(e.to_string()))?;
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            record_request(state, record)?;
+            return Err(ApiError::RequestFailed(format!("{e} (request ID: {request_id})")));
+        }
+    };
 
     let status = response.status();
-    let text = response
-        .text()
-        .await
-        .map_err(|e| ApiError::RequestFailed(e.to_string()))?;
+    record.status_code = Some(status.as_u16());
+    record_request(state, record)?;
+    let headers = response.headers();
+
+    if let Some(info) = parse_rate_limit_info(headers) {
+        match state.rate_limit.write() {
+            Ok(mut guard) => *guard = Some(info),
+            Err(e) => return Err(ApiError::Internal(format!("State lock poisoned: {e}"))),
+        }
+    }
+
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(ApiError::RateLimited {
+            retry_after_secs: parse_retry_after(headers),
+        });
+    }
 
     if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
         return Err(ApiError::RequestFailed(format!(
-            "HTTP {status}: {text}"
+            "HTTP {status}: {text} (request ID: {request_id})"
         )));
     }
 
+    Ok((response, request_id))
+}
+
+async fn touch_active_account(state: &AppState) {
+    let active_account_id = state.active_account_id.read().await.clone();
+    if let Some(account_id) = active_account_id {
+        // Usage tracking is best-effort and must not fail or delay the request
+        tauri::async_runtime::spawn_blocking(move || {
+            let _ = touch_account_last_used(&account_id);
+        });
+    }
+}
+
+async fn make_request(
+    state: &State<'_, AppState>,
+    method: reqwest::Method,
+    endpoint: &str,
+    body: Option<RequestBody>,
+) -> Result<String, ApiError> {
+    let (response, request_id) = send_request(state, method, endpoint, body).await?;
+
+    let text = response
+        .text()
+        .await
+        .map_err(|e| ApiError::RequestFailed(format!("{e} (request ID: {request_id})")))?;
+
+    touch_active_account(state).await;
+
     Ok(text)
 }
 
@@ -84,21 +347,182 @@ pub async fn api_get(endpoint: String, state: State<'_, AppState>) -> Result<Str
     make_request(&state, reqwest::Method::GET, &endpoint, None).await
 }
 
+/// Like `api_get`, but forwards the body over `channel` as it arrives instead of buffering it
+#[tauri::command]
+pub async fn api_get_stream(
+    endpoint: String,
+    channel: Channel<StreamChunk>,
+    max_bytes: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<(), ApiError> {
+    let send = |chunk: StreamChunk| {
+        channel
+            .send(chunk)
+            .map_err(|e| ApiError::Internal(format!("Failed to send stream chunk: {e}")))
+    };
+    let fail = |error: ApiError| {
+        send(StreamChunk {
+            error: Some(error.to_string()),
+            ..StreamChunk::default()
+        })?;
+        Err(error)
+    };
+
+    let (response, request_id) =
+        match send_request(&state, reqwest::Method::GET, &endpoint, None).await {
+            Ok(sent) => sent,
+            Err(e) => return fail(e),
+        };
+
+    if let (Some(max_bytes), Some(length)) = (max_bytes, response.content_length()) {
+        if length > max_bytes {
+            return fail(ApiError::ResponseTooLarge { max_bytes });
+        }
+    }
+
+    let mut received: u64 = 0;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let bytes = match chunk {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return fail(ApiError::RequestFailed(format!(
+                    "{e} (request ID: {request_id})"
+                )))
+            }
+        };
+
+        received = received.saturating_add(bytes.len() as u64);
+        if let Some(max_bytes) = max_bytes {
+            if received > max_bytes {
+                return fail(ApiError::ResponseTooLarge { max_bytes });
+            }
+        }
+
+        send(StreamChunk {
+            data: bytes.to_vec(),
+            ..StreamChunk::default()
+        })?;
+    }
+
+    send(StreamChunk {
+        done: true,
+        ..StreamChunk::default()
+    })?;
+
+    touch_active_account(&state).await;
+
+    Ok(())
+}
+
+/// GET with an in-memory LRU cache; cached responses younger than `max_age_secs` are
+/// returned without contacting the server
+#[tauri::command]
+pub async fn api_get_cached(
+    endpoint: String,
+    max_age_secs: u64,
+    state: State<'_, AppState>,
+) -> Result<String, ApiError> {
+    let account = state
+        .active_account_id
+        .read()
+        .await
+        .clone()
+        .unwrap_or_default();
+    // Scope entries to the account so switching accounts never serves another user's data
+    let cache_key = format!("{account}:{endpoint}");
+    let max_age = std::time::Duration::from_secs(max_age_secs);
+
+    {
+        let mut cache = state
+            .api_cache
+            .0
+            .lock()
+            .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?;
+        if let Some((body, fetched_at)) = cache.get(&cache_key) {
+            if fetched_at.elapsed() <= max_age {
+                return Ok(body.clone());
+            }
+        }
+    }
+
+    let body = make_request(&state, reqwest::Method::GET, &endpoint, None).await?;
+
+    state
+        .api_cache
+        .0
+        .lock()
+        .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?
+        .put(cache_key, (body.clone(), std::time::Instant::now()));
+
+    Ok(body)
+}
+
 #[tauri::command]
 pub async fn api_post(
     endpoint: String,
     body: Option<String>,
+    content_type: Option<String>,
+    body_form: Option<HashMap<String, String>>,
     state: State<'_, AppState>,
 ) -> Result<String, ApiError> {
+    let body = RequestBody::from_args(body, content_type, body_form)?;
     make_request(&state, reqwest::Method::POST, &endpoint, body).await
 }
 
+/// POST a file together with JSON metadata as `multipart/form-data`: a `metadata` part
+/// (`application/json`) and the file under `file_field`
+#[tauri::command]
+pub async fn api_post_multipart_json(
+    endpoint: String,
+    metadata: String,
+    file_path: String,
+    file_field: String,
+    state: State<'_, AppState>,
+) -> Result<String, ApiError> {
+    serde_json::from_str::<serde_json::Value>(&metadata)
+        .map_err(|e| ApiError::InvalidRequest(format!("metadata is not valid JSON: {e}")))?;
+
+    let path = std::path::Path::new(&file_path);
+    let contents = tokio::fs::read(path)
+        .await
+        .map_err(|e| ApiError::InvalidRequest(format!("Failed to read {file_path}: {e}")))?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "file".to_string());
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+    let metadata_part = reqwest::multipart::Part::text(metadata)
+        .mime_str("application/json")
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let file_part = reqwest::multipart::Part::bytes(contents)
+        .file_name(file_name)
+        .mime_str(mime.essence_str())
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let form = reqwest::multipart::Form::new()
+        .part("metadata", metadata_part)
+        .part(file_field, file_part);
+
+    make_request(
+        &state,
+        reqwest::Method::POST,
+        &endpoint,
+        Some(RequestBody::Multipart(form)),
+    )
+    .await
+}
+
 #[tauri::command]
 pub async fn api_put(
     endpoint: String,
     body: Option<String>,
+    content_type: Option<String>,
+    body_form: Option<HashMap<String, String>>,
     state: State<'_, AppState>,
 ) -> Result<String, ApiError> {
+    let body = RequestBody::from_args(body, content_type, body_form)?;
     make_request(&state, reqwest::Method::PUT, &endpoint, body).await
 }
 
@@ -106,8 +530,11 @@ pub async fn api_put(
 pub async fn api_patch(
     endpoint: String,
     body: Option<String>,
+    content_type: Option<String>,
+    body_form: Option<HashMap<String, String>>,
     state: State<'_, AppState>,
 ) -> Result<String, ApiError> {
+    let body = RequestBody::from_args(body, content_type, body_form)?;
     make_request(&state, reqwest::Method::PATCH, &endpoint, body).await
 }
 
@@ -115,3 +542,356 @@ pub async fn api_patch(
 pub async fn api_delete(endpoint: String, state: State<'_, AppState>) -> Result<String, ApiError> {
     make_request(&state, reqwest::Method::DELETE, &endpoint, None).await
 }
+
+/// Open a connection to the server ahead of the first real request, returning
+/// the round-trip time in milliseconds
+#[tauri::command]
+pub async fn warm_connection(state: State<'_, AppState>) -> Result<u64, ApiError> {
+    let server_url = state
+        .server_url
+        .read()
+        .await
+        .clone()
+        .ok_or_else(|| ApiError::NotConfigured("Server URL not set".to_string()))?;
+
+    let client = get_client(&state)?;
+    let started = std::time::Instant::now();
+
+    // Any response means the TCP/TLS handshake is done, so the status is not checked
+    client
+        .head(format!("{server_url}/api/health"))
+        .send()
+        .await
+        .map_err(|e| ApiError::RequestFailed(e.to_string()))?;
+
+    Ok(u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX))
+}
+
+/// DELETE with a JSON body, for endpoints such as bulk deletes
+#[tauri::command]
+pub async fn api_delete_with_body(
+    endpoint: String,
+    body: String,
+    state: State<'_, AppState>,
+) -> Result<String, ApiError> {
+    make_request(
+        &state,
+        reqwest::Method::DELETE,
+        &endpoint,
+        Some(RequestBody::json(body)),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_server_capabilities(
+    state: State<'_, AppState>,
+) -> Result<ServerCapabilities, ApiError> {
+    let text = make_request(&state, reqwest::Method::GET, "/capabilities", None).await?;
+
+    serde_json::from_str(&text)
+        .map_err(|e| ApiError::RequestFailed(format!("Invalid capabilities response: {e}")))
+}
+
+fn with_query_param(endpoint: &str, name: &str, value: &str) -> String {
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    format!(
+        "{endpoint}{separator}{}={}",
+        urlencoding::encode(name),
+        urlencoding::encode(value)
+    )
+}
+
+/// GET a paginated list endpoint and concatenate the `items` of up to `max_pages` pages
+#[tauri::command]
+pub async fn api_get_paginated(
+    endpoint: String,
+    pagination_style: PaginationStyle,
+    max_pages: u8,
+    state: State<'_, AppState>,
+) -> Result<Vec<serde_json::Value>, ApiError> {
+    let mut all_items = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    for page in 1..=u32::from(max_pages) {
+        let page_endpoint = match &pagination_style {
+            PaginationStyle::Cursor { cursor_param, .. } => match &cursor {
+                Some(cursor) => with_query_param(&endpoint, cursor_param, cursor),
+                None => endpoint.clone(),
+            },
+            PaginationStyle::Offset {
+                page_param,
+                page_size_param,
+                page_size,
+            } => {
+                let with_page = with_query_param(&endpoint, page_param, &page.to_string());
+                with_query_param(&with_page, page_size_param, &page_size.to_string())
+            }
+        };
+
+        let text = make_request(&state, reqwest::Method::GET, &page_endpoint, None).await?;
+        let mut json: serde_json::Value = serde_json::from_str(&text)
+            .map_err(|e| ApiError::RequestFailed(format!("Invalid paginated response: {e}")))?;
+
+        let items = match json.get_mut("items").map(serde_json::Value::take) {
+            Some(serde_json::Value::Array(items)) => items,
+            _ => {
+                return Err(ApiError::RequestFailed(
+                    "Paginated response has no items array".to_string(),
+                ))
+            }
+        };
+        let page_len = items.len();
+        all_items.extend(items);
+
+        match &pagination_style {
+            PaginationStyle::Cursor { cursor_field, .. } => {
+                cursor = match json.get(cursor_field) {
+                    Some(serde_json::Value::String(next)) if !next.is_empty() => Some(next.clone()),
+                    Some(serde_json::Value::Number(next)) => Some(next.to_string()),
+                    _ => None,
+                };
+                if cursor.is_none() {
+                    break;
+                }
+            }
+            PaginationStyle::Offset { page_size, .. } => {
+                if page_len < usize::try_from(*page_size).unwrap_or(usize::MAX) {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(all_items)
+}
+
+/// POST `items` as a JSON array to a batch endpoint and return the per-item results.
+/// Fails if any result item is an `{ "error": ... }` object.
+#[tauri::command]
+pub async fn api_batch_post(
+    endpoint: String,
+    items: Vec<serde_json::Value>,
+    state: State<'_, AppState>,
+) -> Result<Vec<serde_json::Value>, ApiError> {
+    let body = serde_json::to_string(&items)
+        .map_err(|e| ApiError::Internal(format!("Failed to serialize batch: {e}")))?;
+
+    let text = make_request(
+        &state,
+        reqwest::Method::POST,
+        &endpoint,
+        Some(RequestBody::json(body)),
+    )
+    .await?;
+
+    let results: Vec<serde_json::Value> = serde_json::from_str(&text)
+        .map_err(|e| ApiError::RequestFailed(format!("Invalid batch response: {e}")))?;
+
+    let errors: Vec<String> = results
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| {
+            let error = item.get("error")?;
+            let message = error
+                .as_str()
+                .map_or_else(|| error.to_string(), ToString::to_string);
+            Some(format!("item {idx}: {message}"))
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(ApiError::RequestFailed(format!(
+            "Batch items failed: {}",
+            errors.join("; ")
+        )));
+    }
+
+    Ok(results)
+}
+
+/// Send independent API requests concurrently (at most `max_concurrent` at a time, 1-16).
+/// Never fails as a whole: per-request errors are reported in each result, and results
+/// are returned in the same order as `requests`.
+#[tauri::command]
+pub async fn api_batch(
+    requests: Vec<BatchRequest>,
+    max_concurrent: u8,
+    state: State<'_, AppState>,
+) -> Result<Vec<BatchResult>, ApiError> {
+    let concurrency = usize::from(max_concurrent.clamp(1, MAX_BATCH_CONCURRENCY));
+    let state = &state;
+
+    let results = stream::iter(requests)
+        .map(|req| async move {
+            let result = match reqwest::Method::from_bytes(req.method.to_uppercase().as_bytes()) {
+                Ok(method) => {
+                    let body = req.body.map(RequestBody::json);
+                    make_request(state, method, &req.endpoint, body).await
+                }
+                Err(_) => Err(ApiError::RequestFailed(format!(
+                    "Unsupported HTTP method: {}",
+                    req.method
+                ))),
+            };
+
+            match result {
+                Ok(response) => BatchResult {
+                    id: req.id,
+                    response: Some(response),
+                    error: None,
+                },
+                Err(e) => BatchResult {
+                    id: req.id,
+                    response: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    Ok(results)
+}
+
+/// Rebuild the shared HTTP client with a new request timeout
+#[tauri::command]
+pub async fn reconfigure_http_client(
+    timeout_secs: u64,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if timeout_secs == 0 {
+        return Err("Timeout must be at least 1 second".to_string());
+    }
+    state.set_http_timeout(timeout_secs)
+}
+
+/// Point API requests at another server without creating an account. Debug builds only.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn set_server_url(url: String, state: State<'_, AppState>) -> Result<(), ApiError> {
+    let parsed = url::Url::parse(&url)
+        .map_err(|e| ApiError::InvalidRequest(format!("Invalid server URL: {e}")))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(ApiError::InvalidRequest("Server URL must use http or https".to_string()));
+    }
+
+    *state.server_url.write().await = Some(url.trim_end_matches('/').to_string());
+    // Cached responses belong to the previous server
+    state
+        .api_cache
+        .0
+        .lock()
+        .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?
+        .clear();
+    Ok(())
+}
+
+/// Use `key` for API requests without saving it to an account. Debug builds only.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn set_api_key(key: String, state: State<'_, AppState>) -> Result<(), ApiError> {
+    *state.api_key.write().await = Some(key);
+    Ok(())
+}
+
+/// Pin the server API version sent with every request; an empty string clears it
+#[tauri::command]
+pub async fn set_api_version_header(
+    version: String,
+    state: State<'_, AppState>,
+) -> Result<(), ApiError> {
+    let version = version.trim();
+    let version = if version.is_empty() {
+        None
+    } else {
+        reqwest::header::HeaderValue::from_str(version).map_err(|_| {
+            ApiError::InvalidRequest(format!("Invalid API version: {version}"))
+        })?;
+        Some(version.to_string())
+    };
+
+    *state.api_version.write().await = version;
+    Ok(())
+}
+
+/// Rate limit status from the most recent API response, if the server reports one
+#[tauri::command]
+pub async fn get_rate_limit_info(
+    state: State<'_, AppState>,
+) -> Result<Option<RateLimitInfo>, ApiError> {
+    let info = state
+        .rate_limit
+        .read()
+        .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?
+        .clone();
+    Ok(info)
+}
+
+/// Whether a server and API key are configured, so the frontend can show a "not
+/// configured" state without a request failing first
+#[tauri::command]
+pub async fn get_app_state_summary(
+    state: State<'_, AppState>,
+) -> Result<AppStateSummary, ApiError> {
+    let server_url = state.server_url.read().await.clone();
+    let server_url_host = server_url
+        .as_deref()
+        .and_then(|url| url::Url::parse(url).ok())
+        .and_then(|url| url.host_str().map(str::to_string));
+
+    Ok(AppStateSummary {
+        has_server_url: server_url.is_some(),
+        has_api_key: state.api_key.read().await.is_some(),
+        server_url_host,
+    })
+}
+
+/// The most recent API requests, newest first
+#[tauri::command]
+pub async fn get_last_request_ids(
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<RequestRecord>, ApiError> {
+    let log = state
+        .request_log
+        .read()
+        .map_err(|e| ApiError::Internal(format!("State lock poisoned: {e}")))?;
+    Ok(log.iter().rev().take(limit).cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> HashMap<String, String> {
+        HashMap::from([("name".to_string(), "value".to_string())])
+    }
+
+    #[test]
+    fn request_body_from_json_only() {
+        let body = RequestBody::from_args(Some("{}".to_string()), None, None);
+        assert!(matches!(
+            body,
+            Ok(Some(RequestBody::Text { ref body, content_type: None })) if body == "{}"
+        ));
+    }
+
+    #[test]
+    fn request_body_from_form_only() {
+        let body = RequestBody::from_args(None, None, Some(form()));
+        assert!(matches!(body, Ok(Some(RequestBody::Form(ref fields))) if fields == &form()));
+    }
+
+    #[test]
+    fn request_body_rejects_body_and_form_together() {
+        let body = RequestBody::from_args(Some("{}".to_string()), None, Some(form()));
+        assert!(matches!(body, Err(ApiError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn request_body_is_none_without_arguments() {
+        assert!(matches!(RequestBody::from_args(None, None, None), Ok(None)));
+    }
+}
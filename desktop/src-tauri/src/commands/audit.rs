@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const AUDIT_LOG_FILE: &str = "audit.log";
+const MAX_AUDIT_LOG_BYTES: u64 = 1024 * 1024;
+/// Rotated files kept as `audit.log.1` (newest) to `audit.log.3` (oldest)
+const AUDIT_LOG_ROTATIONS: u32 = 3;
+
+/// Serializes appends and rotation between concurrent commands
+static AUDIT_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    SignIn,
+    SignOut,
+    ApiKeyCreated,
+    AccountSwitched,
+}
+
+/// One line of the audit log
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEvent {
+    pub timestamp: String,
+    pub event_type: AuditEventType,
+    pub account_id: Option<String>,
+    pub details: String,
+}
+
+impl AuditEvent {
+    pub fn new(event_type: AuditEventType, account_id: Option<String>, details: String) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            event_type,
+            account_id,
+            details,
+        }
+    }
+}
+
+pub(crate) fn audit_log_path(app: &AppHandle) -> Result<PathBuf, std::io::Error> {
+    let app_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    fs::create_dir_all(&app_dir)?;
+    Ok(app_dir.join(AUDIT_LOG_FILE))
+}
+
+fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    path.with_file_name(format!("{AUDIT_LOG_FILE}.{n}"))
+}
+
+/// Shift `audit.log` -> `.1` -> `.2` -> `.3`, dropping the oldest
+fn rotate(path: &Path) -> Result<(), std::io::Error> {
+    let oldest = rotated_path(path, AUDIT_LOG_ROTATIONS);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for n in (1..AUDIT_LOG_ROTATIONS).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
+}
+
+/// Append `event` to the audit log as a JSON line, rotating once the file exceeds 1 MB
+pub fn log_event(app: &AppHandle, event: &AuditEvent) -> Result<(), std::io::Error> {
+    let path = audit_log_path(app)?;
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+
+    let _guard = AUDIT_LOCK
+        .lock()
+        .map_err(|e| std::io::Error::other(format!("Audit log lock poisoned: {e}")))?;
+
+    if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_AUDIT_LOG_BYTES) {
+        rotate(&path)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(line.as_bytes())
+}
+
+/// `log_event` for call sites where auditing must not fail the operation itself
+pub fn record(
+    app: &AppHandle,
+    event_type: AuditEventType,
+    account_id: Option<String>,
+    details: String,
+) {
+    if let Err(e) = log_event(app, &AuditEvent::new(event_type, account_id, details)) {
+        eprintln!("Failed to write audit log: {e}");
+    }
+}
+
+/// The most recent `limit` audit events, oldest first. Continues into rotated files
+/// when the current log is shorter than `limit`.
+#[tauri::command]
+pub async fn get_audit_log(limit: usize, app: AppHandle) -> Result<Vec<AuditEvent>, String> {
+    let path = audit_log_path(&app).map_err(|e| e.to_string())?;
+    let files = std::iter::once(path.clone())
+        .chain((1..=AUDIT_LOG_ROTATIONS).map(|n| rotated_path(&path, n)));
+
+    let mut events = Vec::new();
+    for file in files {
+        if events.len() >= limit {
+            break;
+        }
+        let Ok(contents) = fs::read_to_string(&file) else {
+            continue;
+        };
+        // Skip lines that fail to parse (e.g. a write cut short by a crash)
+        let remaining = limit - events.len();
+        events.extend(
+            contents
+                .lines()
+                .rev()
+                .filter_map(|line| serde_json::from_str::<AuditEvent>(line).ok())
+                .take(remaining),
+        );
+    }
+
+    events.reverse();
+    Ok(events)
+}
@@ -1,11 +1,43 @@
+use crate::commands::audit::{self, AuditEventType};
+use crate::commands::error::MultiError;
+use crate::commands::events::{emit_app_event, AppEvent};
+use crate::commands::oidc::ApiKeyResponse;
+use crate::commands::settings::{self, AppSettings};
+use crate::commands::tray;
 use crate::commands::AppState;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
 use keyring::Entry;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use tokio::task::JoinSet;
+use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 
-const SERVICE_NAME: &str = "com.relate.mail.desktop";
+pub(crate) const SERVICE_NAME: &str = "com.relate.mail.desktop";
 const ACCOUNTS_KEY: &str = "accounts";
+// Stored accounts JSON is followed by this separator and a SHA-256 checksum of the JSON
+const CHECKSUM_SEPARATOR: &str = "\n---\n";
+const LAST_USED_DEBOUNCE: Duration = Duration::from_secs(60);
+const MAX_GROUP_NAME_CHARS: usize = 50;
+
+/// Held across every load-modify-save of the stored accounts so concurrent commands
+/// can't overwrite each other's changes
+static ACCOUNTS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+/// Account ID -> when `touch_account_last_used` last wrote it
+static LAST_TOUCHED: LazyLock<Mutex<HashMap<String, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Encrypted backup layout: MAGIC | salt | nonce | AES-256-GCM ciphertext
+const BACKUP_MAGIC: &[u8; 8] = b"RMACCT01";
+const BACKUP_SALT_LEN: usize = 16;
+const BACKUP_NONCE_LEN: usize = 12;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AuthError {
@@ -15,16 +47,62 @@ pub enum AuthError {
     SerializationError(String),
     #[error("Account not found: {0}")]
     AccountNotFound(String),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("IO error: {0}")]
+    IoError(String),
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+    #[error("Stored account data is corrupted: {0}")]
+    DataCorruption(String),
+    #[error("Request failed: {0}")]
+    RequestFailed(String),
+    #[error("Reset incomplete: {0}")]
+    ResetIncomplete(String),
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
+impl AuthError {
+    /// Short, actionable message suitable for showing to the user. The `Display`
+    /// output keeps the technical detail for logs.
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            AuthError::KeyringError(_) => {
+                "Could not access secure storage — is your keychain locked?"
+            }
+            AuthError::SerializationError(_) => "Account data could not be read or saved.",
+            AuthError::AccountNotFound(_) => "That account no longer exists.",
+            AuthError::InvalidInput(_) => "Some of the information provided is invalid.",
+            AuthError::IoError(_) => {
+                "Could not read or write the file. Check the path and permissions."
+            }
+            AuthError::EncryptionError(_) => {
+                "Could not decrypt the data — is the passphrase correct?"
+            }
+            AuthError::DataCorruption(_) => {
+                "Saved account data is damaged. You may need to sign in again."
+            }
+            AuthError::RequestFailed(_) => {
+                "Could not reach the server. Check your connection and try again."
+            }
+            AuthError::ResetIncomplete(_) => "Some data could not be removed. Try resetting again.",
+            AuthError::Internal(_) => "Something went wrong. Please restart the app and try again.",
+        }
+    }
+}
+
 impl serde::Serialize for AuthError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("AuthError", 2)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("user_message", self.user_message())?;
+        state.end()
     }
 }
 
@@ -38,6 +116,13 @@ pub struct Account {
     pub scopes: Vec<String>,
     pub created_at: String,
     pub last_used_at: String,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub avatar_emoji: Option<String>,
+    /// Workspace groups (e.g. "work", "personal"), lowercased
+    #[serde(default)]
+    pub groups: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -46,6 +131,41 @@ pub struct AccountsData {
     pub active_account_id: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AccountValidation {
+    pub valid: bool,
+    pub error: Option<String>,
+    pub http_status: Option<u16>,
+}
+
+/// Details of an account's API key as returned when it was created; never includes the
+/// key itself
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiKeyMetadata {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// An account's API key metadata for the key overview; never includes the key itself
+#[derive(Serialize, Clone, Debug)]
+pub struct ApiKeyWithAccount {
+    pub account_id: String,
+    pub account_display_name: String,
+    pub metadata: Option<ApiKeyMetadata>,
+}
+
+/// Plaintext contents of an encrypted accounts backup
+#[derive(Serialize, Deserialize)]
+struct AccountsBackup {
+    accounts: Vec<Account>,
+    active_account_id: Option<String>,
+    api_keys: HashMap<String, String>,
+}
+
 // Legacy credential structure for migration
 #[derive(Serialize, Deserialize)]
 pub struct Credentials {
@@ -63,15 +183,58 @@ fn get_api_key_entry(account_id: &str) -> Result<Entry, AuthError> {
         .map_err(|e| AuthError::KeyringError(e.to_string()))
 }
 
-fn load_accounts_data() -> Result<AccountsData, AuthError> {
+// Kept in its own entry so reading metadata never touches the secret
+fn get_api_key_meta_entry(account_id: &str) -> Result<Entry, AuthError> {
+    Entry::new(SERVICE_NAME, &format!("api_key_meta_{account_id}"))
+        .map_err(|e| AuthError::KeyringError(e.to_string()))
+}
+
+fn is_valid_hex_color(color: &str) -> bool {
+    color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_single_grapheme(s: &str) -> bool {
+    s.graphemes(true).count() == 1
+}
+
+/// Deterministic fallback color for an account without an explicit one
+fn account_color_from_id(account_id: &str) -> String {
+    let hash = Sha256::digest(account_id.as_bytes());
+    format!("#{:02X}{:02X}{:02X}", hash[0], hash[1], hash[2])
+}
+
+fn accounts_checksum(json: &str) -> String {
+    Sha256::digest(json.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub(crate) fn load_accounts_data() -> Result<AccountsData, AuthError> {
     let entry = get_accounts_entry()?;
 
-    match entry.get_password() {
-        Ok(json) => serde_json::from_str(&json)
-            .map_err(|e| AuthError::SerializationError(e.to_string())),
-        Err(keyring::Error::NoEntry) => Ok(AccountsData::default()),
-        Err(e) => Err(AuthError::KeyringError(e.to_string())),
-    }
+    let stored = match entry.get_password() {
+        Ok(stored) => stored,
+        Err(keyring::Error::NoEntry) => return Ok(AccountsData::default()),
+        Err(e) => return Err(AuthError::KeyringError(e.to_string())),
+    };
+
+    // Data written before checksums were introduced has no separator
+    let json = match stored.rsplit_once(CHECKSUM_SEPARATOR) {
+        Some((json, checksum)) => {
+            if accounts_checksum(json) != checksum.trim() {
+                return Err(AuthError::DataCorruption(
+                    "checksum mismatch, restore accounts from a backup".to_string(),
+                ));
+            }
+            json
+        }
+        None => stored.as_str(),
+    };
+
+    serde_json::from_str(json).map_err(|e| AuthError::SerializationError(e.to_string()))
 }
 
 fn save_accounts_data(data: &AccountsData) -> Result<(), AuthError> {
@@ -79,12 +242,13 @@ fn save_accounts_data(data: &AccountsData) -> Result<(), AuthError> {
     let json = serde_json::to_string(data)
         .map_err(|e| AuthError::SerializationError(e.to_string()))?;
 
+    let checksum = accounts_checksum(&json);
     entry
-        .set_password(&json)
+        .set_password(&format!("{json}{CHECKSUM_SEPARATOR}{checksum}"))
         .map_err(|e| AuthError::KeyringError(e.to_string()))
 }
 
-fn get_api_key_for_account(account_id: &str) -> Result<Option<String>, AuthError> {
+pub(crate) fn get_api_key_for_account(account_id: &str) -> Result<Option<String>, AuthError> {
     let entry = get_api_key_entry(account_id)?;
 
     match entry.get_password() {
@@ -105,14 +269,145 @@ fn delete_api_key_for_account(account_id: &str) -> Result<(), AuthError> {
     let entry = get_api_key_entry(account_id)?;
     // Ignore error if entry doesn't exist
     let _ = entry.delete_credential();
+    let _ = get_api_key_meta_entry(account_id)?.delete_credential();
+    Ok(())
+}
+
+fn load_api_key_metadata(account_id: &str) -> Result<Option<ApiKeyMetadata>, AuthError> {
+    let entry = get_api_key_meta_entry(account_id)?;
+
+    match entry.get_password() {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| AuthError::SerializationError(e.to_string())),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AuthError::KeyringError(e.to_string())),
+    }
+}
+
+fn save_api_key_metadata(account_id: &str, metadata: &ApiKeyMetadata) -> Result<(), AuthError> {
+    let json =
+        serde_json::to_string(metadata).map_err(|e| AuthError::SerializationError(e.to_string()))?;
+    get_api_key_meta_entry(account_id)?
+        .set_password(&json)
+        .map_err(|e| AuthError::KeyringError(e.to_string()))
+}
+
+async fn set_app_state(
+    state: &AppState,
+    server_url: Option<String>,
+    api_key: Option<String>,
+    account_id: Option<String>,
+) {
+    *state.server_url.write().await = server_url;
+    *state.api_key.write().await = api_key;
+    *state.active_account_id.write().await = account_id;
+}
+
+/// Keep the tray's account switcher in step with the stored accounts. Best-effort:
+/// the account change itself has already succeeded.
+fn refresh_tray(app: &AppHandle) {
+    if let Err(e) = tray::rebuild_tray_menu(app) {
+        eprintln!("Failed to rebuild tray menu: {e}");
+    }
+}
+
+/// Record that an account was just used, writing at most once per minute per account.
+/// Blocks on keyring I/O, so call it from a blocking task rather than the async runtime.
+pub(crate) fn touch_account_last_used(account_id: &str) -> Result<(), AuthError> {
+    {
+        let mut last_touched = LAST_TOUCHED
+            .lock()
+            .map_err(|e| AuthError::Internal(format!("Lock poisoned: {e}")))?;
+        let now = Instant::now();
+        if let Some(last) = last_touched.get(account_id) {
+            if now.duration_since(*last) < LAST_USED_DEBOUNCE {
+                return Ok(());
+            }
+        }
+        last_touched.insert(account_id.to_string(), now);
+    }
+
+    let _accounts_guard = ACCOUNTS_LOCK.blocking_lock();
+    let mut data = load_accounts_data()?;
+    if let Some(acc) = data.accounts.iter_mut().find(|a| a.id == account_id) {
+        acc.last_used_at = chrono::Utc::now().to_rfc3339();
+        save_accounts_data(&data)?;
+    }
     Ok(())
 }
 
+fn derive_backup_key(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, AuthError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AuthError::EncryptionError(e.to_string()))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|e| AuthError::EncryptionError(e.to_string()))
+}
+
+fn is_more_recently_used(candidate: &Account, current: &Account) -> bool {
+    let parse = |a: &Account| chrono::DateTime::parse_from_rfc3339(&a.last_used_at).ok();
+    match (parse(candidate), parse(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Merge two sets of accounts (e.g. from different devices) without any I/O.
+///
+/// Accounts are identified by `(server_url, user_email)`; when both sides have the
+/// same account the most recently used copy wins, with ties going to `local`. All
+/// other accounts from both sides are kept. The local active account is preferred,
+/// then the remote one, then the first merged account.
+pub fn merge_accounts_data(local: AccountsData, remote: AccountsData) -> AccountsData {
+    let mut accounts: Vec<Account> = Vec::new();
+    // Maps every input account ID to the ID of the account that represents it
+    let mut id_map: HashMap<String, String> = HashMap::new();
+
+    for account in local.accounts.into_iter().chain(remote.accounts) {
+        let existing = accounts.iter_mut().find(|a| {
+            a.server_url == account.server_url && a.user_email == account.user_email
+        });
+
+        match existing {
+            Some(existing) if is_more_recently_used(&account, existing) => {
+                let replaced_id = std::mem::replace(existing, account).id;
+                for target in id_map.values_mut() {
+                    if *target == replaced_id {
+                        target.clone_from(&existing.id);
+                    }
+                }
+                id_map.insert(replaced_id, existing.id.clone());
+                id_map.insert(existing.id.clone(), existing.id.clone());
+            }
+            Some(existing) => {
+                id_map.insert(account.id, existing.id.clone());
+            }
+            None => {
+                id_map.insert(account.id.clone(), account.id.clone());
+                accounts.push(account);
+            }
+        }
+    }
+
+    let resolve = |id: Option<String>| id.and_then(|id| id_map.get(&id).cloned());
+    let active_account_id = resolve(local.active_account_id)
+        .or_else(|| resolve(remote.active_account_id))
+        .or_else(|| accounts.first().map(|a| a.id.clone()));
+
+    AccountsData {
+        accounts,
+        active_account_id,
+    }
+}
+
 /// Load all accounts and return with active account info
 #[tauri::command]
 pub async fn load_accounts(
     state: State<'_, AppState>,
 ) -> Result<AccountsData, AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
     let mut data = load_accounts_data()?;
 
     // Auto-select first account if none is active but accounts exist
@@ -125,14 +420,12 @@ pub async fn load_accounts(
     if let Some(active_id) = &data.active_account_id {
         if let Some(account) = data.accounts.iter().find(|a| &a.id == active_id) {
             if let Some(api_key) = get_api_key_for_account(&account.id)? {
-                match state.server_url.write() {
-                    Ok(mut guard) => *guard = Some(account.server_url.clone()),
-                    Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-                }
-                match state.api_key.write() {
-                    Ok(mut guard) => *guard = Some(api_key),
-                    Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-                }
+                set_app_state(
+                    &state,
+                    Some(account.server_url.clone()),
+                    Some(api_key),
+                    Some(account.id.clone()),
+                ).await;
             }
         }
     }
@@ -151,8 +444,11 @@ pub async fn get_account_api_key(account_id: String) -> Result<Option<String>, A
 pub async fn save_account(
     account: Account,
     api_key: String,
+    api_key_metadata: Option<ApiKeyMetadata>,
     state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<AccountsData, AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
     let mut data = load_accounts_data()?;
 
     // Check if account with same server_url and user_email already exists
@@ -189,14 +485,30 @@ pub async fn save_account(
         .ok_or_else(|| AuthError::Internal("active_account_id should be set".to_string()))?;
     let active_account = data.accounts.iter().find(|a| &a.id == active_id)
         .ok_or_else(|| AuthError::Internal("active account not found in list".to_string()))?;
-    match state.server_url.write() {
-        Ok(mut guard) => *guard = Some(active_account.server_url.clone()),
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-    }
-    match state.api_key.write() {
-        Ok(mut guard) => *guard = Some(api_key),
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
+
+    if let Some(metadata) = &api_key_metadata {
+        save_api_key_metadata(&active_account.id, metadata)?;
     }
+    set_app_state(
+        &state,
+        Some(active_account.server_url.clone()),
+        Some(api_key),
+        Some(active_id.clone()),
+    ).await;
+
+    refresh_tray(&app);
+    audit::record(
+        &app,
+        AuditEventType::SignIn,
+        Some(active_account.id.clone()),
+        format!("{} on {}", active_account.user_email, active_account.server_url),
+    );
+    let event = if existing_idx.is_some() {
+        AppEvent::AccountUpdated(active_account.clone())
+    } else {
+        AppEvent::AccountAdded(active_account.clone())
+    };
+    emit_app_event(&state, event);
 
     Ok(data)
 }
@@ -206,7 +518,9 @@ pub async fn save_account(
 pub async fn delete_account(
     account_id: String,
     state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<AccountsData, AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
     let mut data = load_accounts_data()?;
 
     // Remove the account
@@ -216,47 +530,284 @@ pub async fn delete_account(
     delete_api_key_for_account(&account_id)?;
 
     // If we deleted the active account, switch to the first remaining one
-    if data.active_account_id.as_ref() == Some(&account_id) {
+    let active_deleted = data.active_account_id.as_ref() == Some(&account_id);
+    if active_deleted {
         data.active_account_id = data.accounts.first().map(|a| a.id.clone());
 
         // Update AppState
         if let Some(new_active_id) = &data.active_account_id {
             if let Some(account) = data.accounts.iter().find(|a| &a.id == new_active_id) {
                 if let Some(api_key) = get_api_key_for_account(&account.id)? {
-                    match state.server_url.write() {
-                        Ok(mut guard) => *guard = Some(account.server_url.clone()),
-                        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-                    }
-                    match state.api_key.write() {
-                        Ok(mut guard) => *guard = Some(api_key),
-                        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-                    }
+                    set_app_state(
+                        &state,
+                        Some(account.server_url.clone()),
+                        Some(api_key),
+                        Some(account.id.clone()),
+                    ).await;
                 }
             }
         } else {
             // No accounts left, clear AppState
-            match state.server_url.write() {
-                Ok(mut guard) => *guard = None,
-                Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-            }
-            match state.api_key.write() {
-                Ok(mut guard) => *guard = None,
-                Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-            }
+            set_app_state(&state, None, None, None).await;
         }
     }
 
     save_accounts_data(&data)?;
+    refresh_tray(&app);
+    audit::record(
+        &app,
+        AuditEventType::SignOut,
+        Some(account_id.clone()),
+        "Account removed".to_string(),
+    );
+    emit_app_event(&state, AppEvent::AccountRemoved { account_id });
+    if active_deleted {
+        let account_id = data.active_account_id.clone();
+        emit_app_event(&state, AppEvent::ActiveAccountChanged { account_id });
+    }
 
     Ok(data)
 }
 
+/// Delete several accounts and their API keys with a single load/save of the accounts data
+#[tauri::command]
+pub async fn delete_accounts(
+    account_ids: Vec<String>,
+    ignore_missing: bool,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<AccountsData, AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let mut data = load_accounts_data()?;
+
+    // Validate every ID up front so nothing is removed if one is unknown
+    if !ignore_missing {
+        if let Some(missing) = account_ids
+            .iter()
+            .find(|id| !data.accounts.iter().any(|a| &a.id == *id))
+        {
+            return Err(AuthError::AccountNotFound(missing.clone()));
+        }
+    }
+
+    let removed: Vec<String> = data
+        .accounts
+        .iter()
+        .filter(|a| account_ids.contains(&a.id))
+        .map(|a| a.id.clone())
+        .collect();
+
+    // Remove the accounts
+    data.accounts.retain(|a| !account_ids.contains(&a.id));
+
+    // If we deleted the active account, switch to the first remaining one
+    let active_deleted = data
+        .active_account_id
+        .as_ref()
+        .is_some_and(|id| account_ids.contains(id));
+    if active_deleted {
+        data.active_account_id = data.accounts.first().map(|a| a.id.clone());
+    }
+
+    // Save before touching the keyring so a failure can't leave listed accounts without keys
+    save_accounts_data(&data)?;
+
+    // The accounts are gone; a key left behind is only an orphan for
+    // cleanup_orphaned_api_keys, so key deletion is best-effort
+    for account_id in &account_ids {
+        if let Err(e) = delete_api_key_for_account(account_id) {
+            eprintln!("Failed to delete API key for account {account_id}: {e}");
+        }
+    }
+
+    if active_deleted {
+        let new_active = data
+            .active_account_id
+            .as_ref()
+            .and_then(|id| data.accounts.iter().find(|a| &a.id == id));
+        let api_key = new_active.map(|account| get_api_key_for_account(&account.id));
+        match (new_active, api_key) {
+            (Some(account), Some(Ok(Some(api_key)))) => {
+                set_app_state(
+                    &state,
+                    Some(account.server_url.clone()),
+                    Some(api_key),
+                    Some(account.id.clone()),
+                ).await;
+            }
+            // No accounts left, or the new active one has no usable key; never keep
+            // serving the deleted account's credentials
+            _ => set_app_state(&state, None, None, None).await,
+        }
+    }
+
+    refresh_tray(&app);
+    for account_id in removed {
+        audit::record(
+            &app,
+            AuditEventType::SignOut,
+            Some(account_id.clone()),
+            "Account removed".to_string(),
+        );
+        emit_app_event(&state, AppEvent::AccountRemoved { account_id });
+    }
+    if active_deleted {
+        let account_id = data.active_account_id.clone();
+        emit_app_event(&state, AppEvent::ActiveAccountChanged { account_id });
+    }
+
+    Ok(data)
+}
+
+/// Store a newly issued API key for an account, replacing the old one in the keyring,
+/// its metadata and `Account.api_key_id`. Returns the account with its previous key ID.
+pub(crate) async fn replace_account_api_key(
+    account_id: &str,
+    new_key: &ApiKeyResponse,
+    state: &AppState,
+) -> Result<(Account, String), AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let mut data = load_accounts_data()?;
+    let account = data
+        .accounts
+        .iter_mut()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.to_string()))?;
+
+    save_api_key_for_account(account_id, &new_key.api_key)?;
+    let old_key_id = std::mem::replace(&mut account.api_key_id, new_key.id.clone());
+    let updated = account.clone();
+    save_accounts_data(&data)?;
+
+    // Metadata is informational; a failure here shouldn't undo a working key
+    let metadata = ApiKeyMetadata {
+        id: new_key.id.clone(),
+        name: new_key.name.clone(),
+        scopes: new_key.scopes.clone().unwrap_or_else(|| updated.scopes.clone()),
+        created_at: new_key.created_at.clone(),
+        expires_at: None,
+    };
+    if let Err(e) = save_api_key_metadata(account_id, &metadata) {
+        eprintln!("Failed to save API key metadata: {e}");
+    }
+
+    // Requests for the active account must switch to the new key before the old is revoked
+    if state.active_account_id.read().await.as_deref() == Some(account_id) {
+        *state.api_key.write().await = Some(new_key.api_key.clone());
+    }
+
+    Ok((updated, old_key_id))
+}
+
+/// Metadata for an account's API key, if it was saved with the account
+#[tauri::command]
+pub async fn get_api_key_metadata(account_id: String) -> Result<Option<ApiKeyMetadata>, AuthError> {
+    load_api_key_metadata(&account_id)
+}
+
+/// API key metadata for every account, for an overview across devices and servers
+#[tauri::command]
+pub async fn get_all_api_keys() -> Result<Vec<ApiKeyWithAccount>, AuthError> {
+    load_accounts_data()?
+        .accounts
+        .into_iter()
+        .map(|account| {
+            Ok(ApiKeyWithAccount {
+                metadata: load_api_key_metadata(&account.id)?,
+                account_id: account.id,
+                account_display_name: account.display_name,
+            })
+        })
+        .collect()
+}
+
 /// Set the active account and update AppState
 #[tauri::command]
 pub async fn set_active_account(
     account_id: String,
     state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<Account, AuthError> {
+    let account = activate_account(&account_id, &state).await?;
+    audit::record(
+        &app,
+        AuditEventType::AccountSwitched,
+        Some(account.id.clone()),
+        format!("Switched to {}", account.user_email),
+    );
+    Ok(account)
+}
+
+/// Whether `account_id` is the active account, answered from memory without the
+/// keyring. Never fails; the `Result` is only there because async commands that
+/// borrow state must return one.
+#[tauri::command]
+pub async fn is_account_active(
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, AuthError> {
+    Ok(state.active_account_id.read().await.as_deref() == Some(account_id.as_str()))
+}
+
+/// Split the stored account IDs by whether their `api_key_{id}` keyring entry exists.
+/// The keyring can't be enumerated, so entries left behind by deleted accounts can't be
+/// found this way; only accounts whose key has gone missing can.
+fn partition_accounts_by_keyring_entry() -> Result<(Vec<String>, Vec<String>), AuthError> {
+    let mut with_key = Vec::new();
+    let mut without_key = Vec::new();
+    for account in load_accounts_data()?.accounts {
+        if get_api_key_for_account(&account.id)?.is_some() {
+            with_key.push(account.id);
+        } else {
+            without_key.push(account.id);
+        }
+    }
+    Ok((with_key, without_key))
+}
+
+/// IDs of the accounts that have an API key in the keyring. Debug builds only.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn list_keyring_account_ids() -> Result<Vec<String>, AuthError> {
+    Ok(partition_accounts_by_keyring_entry()?.0)
+}
+
+/// Report accounts whose API key is missing from the keyring (e.g. after a crash during
+/// deletion), returning how many there are. Nothing is removed; the user decides whether
+/// to sign in again or delete the account.
+#[tauri::command]
+pub async fn cleanup_orphaned_api_keys() -> Result<usize, AuthError> {
+    let (_, missing) = partition_accounts_by_keyring_entry()?;
+    for account_id in &missing {
+        eprintln!("Warning: account {account_id} has no API key in the keyring");
+    }
+    Ok(missing.len())
+}
+
+/// The active account alone, for UI that doesn't need the whole list. Uses the account
+/// AppState is pointed at, falling back to the persisted choice before one is activated.
+#[tauri::command]
+pub async fn get_active_account(state: State<'_, AppState>) -> Result<Option<Account>, AuthError> {
+    let data = load_accounts_data()?;
+    let Some(active_id) = state
+        .active_account_id
+        .read()
+        .await
+        .clone()
+        .or(data.active_account_id)
+    else {
+        return Ok(None);
+    };
+
+    Ok(data.accounts.into_iter().find(|a| a.id == active_id))
+}
+
+/// Make an account active, persisting the choice and pointing AppState at it
+pub(crate) async fn activate_account(
+    account_id: &str,
+    state: &AppState,
+) -> Result<Account, AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
     let mut data = load_accounts_data()?;
 
     // Find the account
@@ -264,15 +815,15 @@ pub async fn set_active_account(
         .accounts
         .iter()
         .find(|a| a.id == account_id)
-        .ok_or_else(|| AuthError::AccountNotFound(account_id.clone()))?
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.to_string()))?
         .clone();
 
     // Get the API key
-    let api_key = get_api_key_for_account(&account_id)?
+    let api_key = get_api_key_for_account(account_id)?
         .ok_or_else(|| AuthError::KeyringError("API key not found".to_string()))?;
 
     // Update active account
-    data.active_account_id = Some(account_id.clone());
+    data.active_account_id = Some(account_id.to_string());
 
     // Update last_used_at
     if let Some(acc) = data.accounts.iter_mut().find(|a| a.id == account_id) {
@@ -282,16 +833,499 @@ pub async fn set_active_account(
     save_accounts_data(&data)?;
 
     // Update AppState
-    match state.server_url.write() {
-        Ok(mut guard) => *guard = Some(account.server_url.clone()),
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
+    set_app_state(
+        state,
+        Some(account.server_url.clone()),
+        Some(api_key),
+        Some(account.id.clone()),
+    ).await;
+
+    let account_id = Some(account.id.clone());
+    emit_app_event(state, AppEvent::ActiveAccountChanged { account_id });
+
+    Ok(account)
+}
+
+/// Check whether an account's stored API key is still accepted by its server.
+/// Read-only: does not modify AppState or the stored accounts.
+#[tauri::command]
+pub async fn validate_account(
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<AccountValidation, AuthError> {
+    let data = load_accounts_data()?;
+
+    let account = data
+        .accounts
+        .iter()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.clone()))?;
+
+    let client = state.http_client().map_err(AuthError::Internal)?;
+    check_account(account, &client).await
+}
+
+/// Validate every account concurrently, reporting each one that can't reach the
+/// server with its key as a failure
+#[tauri::command]
+pub async fn validate_all_accounts(
+    state: State<'_, AppState>,
+) -> Result<MultiError<AuthError>, AuthError> {
+    let data = load_accounts_data()?;
+    let client = state.http_client().map_err(AuthError::Internal)?;
+
+    let mut tasks = JoinSet::new();
+    let mut task_accounts = HashMap::new();
+    for account in data.accounts {
+        let client = client.clone();
+        let account_id = account.id.clone();
+        let handle = tasks.spawn(async move { check_account(&account, &client).await });
+        task_accounts.insert(handle.id(), account_id);
     }
-    match state.api_key.write() {
-        Ok(mut guard) => *guard = Some(api_key),
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
+
+    let mut outcome = MultiError::default();
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (task_id, result) = match joined {
+            Ok((task_id, result)) => (task_id, result),
+            Err(e) => (
+                e.id(),
+                Err(AuthError::Internal(format!("Validation task failed: {e}"))),
+            ),
+        };
+        let Some(account_id) = task_accounts.remove(&task_id) else {
+            continue;
+        };
+
+        let result = result.and_then(|validation| match validation {
+            AccountValidation { valid: true, .. } => Ok(()),
+            AccountValidation { error, .. } => Err(AuthError::RequestFailed(
+                error.unwrap_or_else(|| "Account is not valid".to_string()),
+            )),
+        });
+        outcome.record(account_id, result);
     }
 
-    Ok(account)
+    Ok(outcome)
+}
+
+async fn check_account(
+    account: &Account,
+    client: &reqwest::Client,
+) -> Result<AccountValidation, AuthError> {
+    let Some(api_key) = get_api_key_for_account(&account.id)? else {
+        return Ok(AccountValidation {
+            valid: false,
+            error: Some("API key not found in keyring".to_string()),
+            http_status: None,
+        });
+    };
+
+    let url = format!("{}/api/profile", account.server_url);
+    let resp = match client.get(&url).header("X-Api-Key", &api_key).send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            return Ok(AccountValidation {
+                valid: false,
+                error: Some(format!("Profile request failed: {e}")),
+                http_status: None,
+            })
+        }
+    };
+
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(AccountValidation {
+            valid: true,
+            error: None,
+            http_status: Some(status.as_u16()),
+        });
+    }
+
+    let error = match status {
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            "API key is no longer valid".to_string()
+        }
+        _ => {
+            let body = resp.text().await.unwrap_or_default();
+            format!("Profile endpoint returned HTTP {status}: {body}")
+        }
+    };
+
+    Ok(AccountValidation {
+        valid: false,
+        error: Some(error),
+        http_status: Some(status.as_u16()),
+    })
+}
+
+/// Update the visual metadata (color and avatar emoji) of an account.
+/// A missing color falls back to the deterministic color derived from the account ID.
+#[tauri::command]
+pub async fn update_account_metadata(
+    account_id: String,
+    color: Option<String>,
+    avatar_emoji: Option<String>,
+) -> Result<Account, AuthError> {
+    if let Some(color) = &color {
+        if !is_valid_hex_color(color) {
+            return Err(AuthError::InvalidInput(format!(
+                "Color must be a hex value like #1A2B3C, got {color}"
+            )));
+        }
+    }
+    if let Some(emoji) = &avatar_emoji {
+        if !is_single_grapheme(emoji) {
+            return Err(AuthError::InvalidInput(
+                "Avatar emoji must be a single character".to_string(),
+            ));
+        }
+    }
+
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let mut data = load_accounts_data()?;
+
+    let account = data
+        .accounts
+        .iter_mut()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.clone()))?;
+
+    account.color = Some(color.unwrap_or_else(|| account_color_from_id(&account_id)));
+    account.avatar_emoji = avatar_emoji;
+    let updated = account.clone();
+
+    save_accounts_data(&data)?;
+
+    Ok(updated)
+}
+
+/// Replace an account's scopes after the user re-authenticates with a different grant
+#[tauri::command]
+pub async fn update_account_scopes(
+    account_id: String,
+    new_scopes: Vec<String>,
+) -> Result<Account, AuthError> {
+    let mut seen = std::collections::HashSet::new();
+    let scopes: Vec<String> = new_scopes
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty() && seen.insert(s.clone()))
+        .collect();
+    if scopes.is_empty() {
+        return Err(AuthError::InvalidInput(
+            "An account needs at least one scope".to_string(),
+        ));
+    }
+
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let mut data = load_accounts_data()?;
+
+    let account = data
+        .accounts
+        .iter_mut()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.clone()))?;
+
+    account.scopes = scopes;
+    let updated = account.clone();
+
+    save_accounts_data(&data)?;
+
+    Ok(updated)
+}
+
+/// The first account signed in as `email` (case-insensitive), on any server
+#[tauri::command]
+pub async fn get_account_by_email(email: String) -> Result<Option<Account>, AuthError> {
+    let email = email.to_lowercase();
+    Ok(load_accounts_data()?
+        .accounts
+        .into_iter()
+        .find(|a| a.user_email.to_lowercase() == email))
+}
+
+/// Every account signed in as `email` (case-insensitive), one per server
+#[tauri::command]
+pub async fn get_accounts_by_email(email: String) -> Result<Vec<Account>, AuthError> {
+    let email = email.to_lowercase();
+    Ok(load_accounts_data()?
+        .accounts
+        .into_iter()
+        .filter(|a| a.user_email.to_lowercase() == email)
+        .collect())
+}
+
+/// Accounts in `group`, or every account when `group` is `None`
+#[tauri::command]
+pub async fn filter_accounts_by_group(group: Option<String>) -> Result<Vec<Account>, AuthError> {
+    let accounts = load_accounts_data()?.accounts;
+    let Some(group) = group else {
+        return Ok(accounts);
+    };
+
+    let group = group.trim().to_lowercase();
+    Ok(accounts
+        .into_iter()
+        .filter(|a| a.groups.contains(&group))
+        .collect())
+}
+
+/// Replace an account's groups. Names are trimmed, lowercased and deduplicated.
+#[tauri::command]
+pub async fn set_account_groups(
+    account_id: String,
+    groups: Vec<String>,
+) -> Result<Account, AuthError> {
+    let mut seen = std::collections::HashSet::new();
+    let mut normalized = Vec::with_capacity(groups.len());
+    for group in groups {
+        let group = group.trim().to_lowercase();
+        if group.is_empty() {
+            return Err(AuthError::InvalidInput("Group names cannot be empty".to_string()));
+        }
+        if group.chars().count() > MAX_GROUP_NAME_CHARS {
+            return Err(AuthError::InvalidInput(format!(
+                "Group names must be at most {MAX_GROUP_NAME_CHARS} characters"
+            )));
+        }
+        if seen.insert(group.clone()) {
+            normalized.push(group);
+        }
+    }
+
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let mut data = load_accounts_data()?;
+
+    let account = data
+        .accounts
+        .iter_mut()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.clone()))?;
+
+    account.groups = normalized;
+    let updated = account.clone();
+
+    save_accounts_data(&data)?;
+
+    Ok(updated)
+}
+
+/// Get the fallback color the app uses for an account without an explicit color
+#[tauri::command]
+pub async fn derive_account_color(account_id: String) -> String {
+    account_color_from_id(&account_id)
+}
+
+/// Accounts ordered by most recent use, for the quick-switch UI
+#[tauri::command]
+pub async fn get_recently_used_accounts(limit: usize) -> Result<Vec<Account>, AuthError> {
+    let mut accounts = load_accounts_data()?.accounts;
+
+    // Accounts with an unparseable timestamp sort last
+    accounts.sort_by_key(|a| {
+        std::cmp::Reverse(chrono::DateTime::parse_from_rfc3339(&a.last_used_at).ok())
+    });
+    accounts.truncate(limit);
+
+    Ok(accounts)
+}
+
+/// Write an encrypted backup of all accounts and their API keys to `dest_path`
+#[tauri::command]
+pub async fn export_accounts(dest_path: String, passphrase: String) -> Result<(), AuthError> {
+    let data = load_accounts_data()?;
+
+    let mut api_keys = HashMap::new();
+    for account in &data.accounts {
+        if let Some(api_key) = get_api_key_for_account(&account.id)? {
+            api_keys.insert(account.id.clone(), api_key);
+        }
+    }
+
+    let backup = AccountsBackup {
+        accounts: data.accounts,
+        active_account_id: data.active_account_id,
+        api_keys,
+    };
+    let plaintext =
+        serde_json::to_vec(&backup).map_err(|e| AuthError::SerializationError(e.to_string()))?;
+
+    let mut rng = rand::thread_rng();
+    let mut salt = [0u8; BACKUP_SALT_LEN];
+    let mut nonce = [0u8; BACKUP_NONCE_LEN];
+    rng.fill(&mut salt);
+    rng.fill(&mut nonce);
+
+    let cipher = derive_backup_key(&passphrase, &salt)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|e| AuthError::EncryptionError(e.to_string()))?;
+
+    let mut out = Vec::with_capacity(
+        BACKUP_MAGIC.len() + BACKUP_SALT_LEN + BACKUP_NONCE_LEN + ciphertext.len(),
+    );
+    out.extend_from_slice(BACKUP_MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+
+    std::fs::write(&dest_path, out).map_err(|e| AuthError::IoError(e.to_string()))
+}
+
+/// Restore accounts from a backup written by `export_accounts`, merging them into
+/// the existing accounts with `merge_accounts_data`
+#[tauri::command]
+pub async fn import_accounts(
+    src_path: String,
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<AccountsData, AuthError> {
+    let contents = std::fs::read(&src_path).map_err(|e| AuthError::IoError(e.to_string()))?;
+
+    let header_len = BACKUP_MAGIC.len() + BACKUP_SALT_LEN + BACKUP_NONCE_LEN;
+    if contents.len() < header_len || !contents.starts_with(BACKUP_MAGIC) {
+        return Err(AuthError::InvalidInput(
+            "File is not a Relate Mail accounts backup".to_string(),
+        ));
+    }
+
+    let (salt, rest) = contents[BACKUP_MAGIC.len()..].split_at(BACKUP_SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(BACKUP_NONCE_LEN);
+
+    let cipher = derive_backup_key(&passphrase, salt)?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            AuthError::EncryptionError("Wrong passphrase or corrupted backup".to_string())
+        })?;
+
+    let backup: AccountsBackup = serde_json::from_slice(&plaintext)
+        .map_err(|e| AuthError::SerializationError(e.to_string()))?;
+
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let local = load_accounts_data()?;
+    let local_ids: Vec<String> = local.accounts.iter().map(|a| a.id.clone()).collect();
+
+    // Accounts without a key in the backup cannot be used, so leave them out
+    let mut remote = AccountsData {
+        accounts: backup.accounts,
+        active_account_id: backup.active_account_id,
+    };
+    remote
+        .accounts
+        .retain(|a| backup.api_keys.contains_key(&a.id));
+
+    let data = merge_accounts_data(local, remote);
+
+    // Store keys for accounts taken from the backup, drop keys of replaced local accounts
+    for account in &data.accounts {
+        if !local_ids.contains(&account.id) {
+            if let Some(api_key) = backup.api_keys.get(&account.id) {
+                save_api_key_for_account(&account.id, api_key)?;
+            }
+        }
+    }
+    for local_id in &local_ids {
+        if !data.accounts.iter().any(|a| &a.id == local_id) {
+            delete_api_key_for_account(local_id)?;
+        }
+    }
+
+    // The active account's key may have been replaced by the import, so refresh AppState
+    if let Some(active_id) = &data.active_account_id {
+        if let Some(account) = data.accounts.iter().find(|a| &a.id == active_id) {
+            if let Some(api_key) = get_api_key_for_account(&account.id)? {
+                set_app_state(
+                    &state,
+                    Some(account.server_url.clone()),
+                    Some(api_key),
+                    Some(account.id.clone()),
+                ).await;
+            }
+        }
+    }
+
+    save_accounts_data(&data)?;
+
+    Ok(data)
+}
+
+/// Replace an account's API key using the account password (for deployments without OIDC).
+/// The old key authenticates the request; the new key is stored only once the server issued it.
+#[tauri::command]
+pub async fn rotate_account_api_key_with_password(
+    account_id: String,
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<Account, AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let mut data = load_accounts_data()?;
+
+    let server_url = data
+        .accounts
+        .iter()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.clone()))?
+        .server_url
+        .clone();
+
+    let old_api_key = get_api_key_for_account(&account_id)?
+        .ok_or_else(|| AuthError::KeyringError("API key not found".to_string()))?;
+
+    let client = state.http_client().map_err(AuthError::Internal)?;
+    let body = serde_json::json!({ "password": password });
+
+    let resp = client
+        .post(format!("{server_url}/api/smtp-credentials/rotate"))
+        .header("X-Api-Key", &old_api_key)
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .map_err(|e| AuthError::RequestFailed(format!("API key rotation failed: {e}")))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(AuthError::RequestFailed(format!(
+            "Rotation endpoint returned HTTP {status}: {body}"
+        )));
+    }
+
+    let new_key: ApiKeyResponse = resp
+        .json()
+        .await
+        .map_err(|e| AuthError::RequestFailed(format!("Invalid API key response: {e}")))?;
+
+    let account = data
+        .accounts
+        .iter_mut()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| AuthError::AccountNotFound(account_id.clone()))?;
+    account.api_key_id = new_key.id;
+    if let Some(scopes) = new_key.scopes {
+        account.scopes = scopes;
+    }
+    let updated = account.clone();
+
+    // Keep the key and the account metadata consistent: roll the key back if saving fails
+    save_api_key_for_account(&account_id, &new_key.api_key)?;
+    if let Err(e) = save_accounts_data(&data) {
+        let _ = save_api_key_for_account(&account_id, &old_api_key);
+        return Err(e);
+    }
+
+    let is_active = data.active_account_id.as_deref() == Some(account_id.as_str());
+    if is_active {
+        set_app_state(
+            &state,
+            Some(updated.server_url.clone()),
+            Some(new_key.api_key),
+            Some(account_id),
+        ).await;
+    }
+
+    Ok(updated)
 }
 
 /// Generate a new unique account ID
@@ -300,6 +1334,157 @@ pub fn generate_account_id() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// Give every account a fresh ID so long-lived IDs can't be used for fingerprinting.
+/// API keys are copied to their new keyring entries and read back before anything old is
+/// removed, so a failure part-way leaves the original accounts intact.
+/// Returns `(old_id, new_id)` pairs so the frontend can update cached references.
+#[tauri::command]
+pub async fn rotate_all_account_ids(
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<Vec<(String, String)>, AuthError> {
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    let mut data = load_accounts_data()?;
+    let id_map: Vec<(String, String)> = data
+        .accounts
+        .iter()
+        .map(|a| (a.id.clone(), Uuid::new_v4().to_string()))
+        .collect();
+
+    // Copy keys to the new IDs and verify them before touching anything else
+    let mut copied: Vec<&str> = Vec::new();
+    for (old_id, new_id) in &id_map {
+        let result = get_api_key_for_account(old_id).and_then(|key| match key {
+            Some(key) => {
+                save_api_key_for_account(new_id, &key)?;
+                copied.push(new_id);
+                if get_api_key_for_account(new_id)?.as_deref() == Some(key.as_str()) {
+                    Ok(())
+                } else {
+                    Err(AuthError::KeyringError(format!(
+                        "API key for account {old_id} did not verify after copying"
+                    )))
+                }
+            }
+            None => Ok(()),
+        });
+        if let Err(e) = result {
+            for new_id in copied {
+                let _ = delete_api_key_for_account(new_id);
+            }
+            return Err(e);
+        }
+    }
+
+    for account in &mut data.accounts {
+        if let Some((_, new_id)) = id_map.iter().find(|(old, _)| *old == account.id) {
+            account.id = new_id.clone();
+        }
+    }
+    if let Some(active) = &data.active_account_id {
+        data.active_account_id = id_map
+            .iter()
+            .find(|(old, _)| old == active)
+            .map(|(_, new_id)| new_id.clone());
+    }
+
+    if let Err(e) = save_accounts_data(&data) {
+        for new_id in copied {
+            let _ = delete_api_key_for_account(new_id);
+        }
+        return Err(e);
+    }
+
+    // The new IDs are committed; stale keys left behind by a failure here are harmless,
+    // and metadata is informational, so copying it is best-effort
+    for (old_id, new_id) in &id_map {
+        if let Ok(Some(metadata)) = load_api_key_metadata(old_id) {
+            let _ = save_api_key_metadata(new_id, &metadata);
+        }
+        let _ = delete_api_key_for_account(old_id);
+    }
+    refresh_tray(&app);
+
+    {
+        let mut active_account_id = state.active_account_id.write().await;
+        if let Some(current) = active_account_id.as_ref() {
+            if let Some((_, new_id)) = id_map.iter().find(|(old, _)| old == current) {
+                *active_account_id = Some(new_id.clone());
+            }
+        }
+    }
+
+    // Cached responses and the last-used debounce are keyed by the old IDs; both only
+    // save work, so a poisoned lock is not worth failing the committed rotation over
+    if let Ok(mut cache) = state.api_cache.0.lock() {
+        cache.clear();
+    }
+    if let Ok(mut last_touched) = LAST_TOUCHED.lock() {
+        last_touched.clear();
+    }
+
+    for account in &data.accounts {
+        emit_app_event(&state, AppEvent::AccountUpdated(account.clone()));
+    }
+    let account_id = data.active_account_id.clone();
+    emit_app_event(&state, AppEvent::ActiveAccountChanged { account_id });
+
+    Ok(id_map)
+}
+
+/// Factory reset: sign out, delete every account, API key and legacy credential, and
+/// restore default settings. Every step is attempted even if an earlier one fails.
+#[tauri::command]
+pub async fn reset_app_state(state: State<'_, AppState>, app: AppHandle) -> Result<(), AuthError> {
+    let mut failures = Vec::new();
+
+    if let Err(e) = state.clear().await {
+        failures.push(e.to_string());
+    }
+
+    let _accounts_guard = ACCOUNTS_LOCK.lock().await;
+    match load_accounts_data() {
+        Ok(data) => {
+            for account in &data.accounts {
+                if let Err(e) = delete_api_key_for_account(&account.id) {
+                    failures.push(e.to_string());
+                }
+            }
+        }
+        Err(e) => failures.push(e.to_string()),
+    }
+
+    match get_accounts_entry() {
+        Ok(entry) => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => failures.push(format!("Keyring error: {e}")),
+        },
+        Err(e) => failures.push(e.to_string()),
+    }
+
+    match Entry::new(SERVICE_NAME, "credentials") {
+        Ok(entry) => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => failures.push(format!("Keyring error: {e}")),
+        },
+        Err(e) => failures.push(format!("Keyring error: {e}")),
+    }
+
+    let defaults = AppSettings::default();
+    if let Err(e) = settings::write_settings(&app, &defaults) {
+        failures.push(e.to_string());
+    }
+    if let Err(e) = settings::sync_app_state(&defaults, &state) {
+        failures.push(e.to_string());
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(AuthError::ResetIncomplete(failures.join("; ")))
+    }
+}
+
 // ============================================================================
 // Legacy commands for backwards compatibility during migration
 // ============================================================================
@@ -328,14 +1513,7 @@ pub async fn save_credentials(
         .map_err(|e| AuthError::KeyringError(e.to_string()))?;
 
     // Update app state
-    match state.server_url.write() {
-        Ok(mut guard) => *guard = Some(server_url),
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-    }
-    match state.api_key.write() {
-        Ok(mut guard) => *guard = Some(api_key),
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-    }
+    set_app_state(&state, Some(server_url), Some(api_key), None).await;
 
     Ok(())
 }
@@ -351,14 +1529,12 @@ pub async fn load_credentials(state: State<'_, AppState>) -> Result<Option<Crede
                 .map_err(|e| AuthError::SerializationError(e.to_string()))?;
 
             // Update app state
-            match state.server_url.write() {
-                Ok(mut guard) => *guard = Some(credentials.server_url.clone()),
-                Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-            }
-            match state.api_key.write() {
-                Ok(mut guard) => *guard = Some(credentials.api_key.clone()),
-                Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
-            }
+            set_app_state(
+                &state,
+                Some(credentials.server_url.clone()),
+                Some(credentials.api_key.clone()),
+                None,
+            ).await;
 
             Ok(Some(credentials))
         }
@@ -376,14 +1552,108 @@ pub async fn clear_credentials(state: State<'_, AppState>) -> Result<(), AuthErr
     let _ = entry.delete_credential();
 
     // Clear app state
-    match state.server_url.write() {
-        Ok(mut guard) => *guard = None,
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
+    set_app_state(&state, None, None, None).await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(id: &str, email: &str, last_used_at: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            display_name: email.to_string(),
+            server_url: "https://mail.example.com".to_string(),
+            user_email: email.to_string(),
+            api_key_id: format!("key-{id}"),
+            scopes: Vec::new(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            last_used_at: last_used_at.to_string(),
+            color: None,
+            avatar_emoji: None,
+            groups: Vec::new(),
+        }
     }
-    match state.api_key.write() {
-        Ok(mut guard) => *guard = None,
-        Err(e) => return Err(AuthError::Internal(format!("State lock poisoned: {e}"))),
+
+    fn data(accounts: Vec<Account>, active: Option<&str>) -> AccountsData {
+        AccountsData {
+            accounts,
+            active_account_id: active.map(ToString::to_string),
+        }
     }
 
-    Ok(())
+    fn ids(data: &AccountsData) -> Vec<&str> {
+        data.accounts.iter().map(|a| a.id.as_str()).collect()
+    }
+
+    const OLDER: &str = "2024-01-01T00:00:00Z";
+    const NEWER: &str = "2024-06-01T00:00:00Z";
+
+    #[test]
+    fn merge_dedupes_by_server_and_email() {
+        let local = data(vec![account("l1", "a@example.com", OLDER)], None);
+        let remote = data(
+            vec![
+                account("r1", "a@example.com", OLDER),
+                account("r2", "b@example.com", OLDER),
+            ],
+            None,
+        );
+        let merged = merge_accounts_data(local, remote);
+        assert_eq!(ids(&merged), vec!["l1", "r2"]);
+    }
+
+    #[test]
+    fn merge_keeps_the_most_recently_used_copy() {
+        let local = data(vec![account("l1", "a@example.com", OLDER)], None);
+        let remote = data(vec![account("r1", "a@example.com", NEWER)], None);
+        let merged = merge_accounts_data(local, remote);
+        assert_eq!(ids(&merged), vec!["r1"]);
+    }
+
+    #[test]
+    fn merge_tie_goes_to_local() {
+        let local = data(vec![account("l1", "a@example.com", NEWER)], None);
+        let remote = data(vec![account("r1", "a@example.com", NEWER)], None);
+        let merged = merge_accounts_data(local, remote);
+        assert_eq!(ids(&merged), vec!["l1"]);
+    }
+
+    #[test]
+    fn merge_remaps_local_active_id_to_the_winning_copy() {
+        let local = data(vec![account("l1", "a@example.com", OLDER)], Some("l1"));
+        let remote = data(vec![account("r1", "a@example.com", NEWER)], None);
+        let merged = merge_accounts_data(local, remote);
+        assert_eq!(merged.active_account_id.as_deref(), Some("r1"));
+    }
+
+    #[test]
+    fn merge_remaps_remote_active_id_to_the_winning_copy() {
+        let local = data(
+            vec![
+                account("l1", "b@example.com", OLDER),
+                account("l2", "a@example.com", NEWER),
+            ],
+            None,
+        );
+        let remote = data(vec![account("r1", "a@example.com", OLDER)], Some("r1"));
+        let merged = merge_accounts_data(local, remote);
+        assert_eq!(merged.active_account_id.as_deref(), Some("l2"));
+    }
+
+    #[test]
+    fn merge_keeps_remote_active_id_when_the_remote_copy_wins() {
+        let local = data(
+            vec![
+                account("l1", "b@example.com", OLDER),
+                account("l2", "a@example.com", OLDER),
+            ],
+            None,
+        );
+        let remote = data(vec![account("r1", "a@example.com", NEWER)], Some("r1"));
+        let merged = merge_accounts_data(local, remote);
+        assert_eq!(merged.active_account_id.as_deref(), Some("r1"));
+    }
 }
@@ -0,0 +1,188 @@
+use crate::commands::settings::{get_settings_sync, write_settings, SettingsError};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+const LAUNCH_NAME: &str = "Relate Mail";
+
+fn current_exe() -> Result<PathBuf, SettingsError> {
+    std::env::current_exe().map_err(|e| SettingsError::IoError(e.to_string()))
+}
+
+/// Run key value under HKCU
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{current_exe, LAUNCH_NAME};
+    use crate::commands::settings::SettingsError;
+    use tauri::AppHandle;
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_SET_VALUE};
+    use winreg::RegKey;
+
+    const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+    pub fn enable(_app: &AppHandle) -> Result<(), SettingsError> {
+        let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+            .create_subkey(RUN_KEY)
+            .map_err(|e| SettingsError::IoError(e.to_string()))?;
+        let command = format!("\"{}\"", current_exe()?.display());
+        key.set_value(LAUNCH_NAME, &command).map_err(|e| SettingsError::IoError(e.to_string()))
+    }
+
+    pub fn disable(_app: &AppHandle) -> Result<(), SettingsError> {
+        let key = match RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey_with_flags(RUN_KEY, KEY_SET_VALUE)
+        {
+            Ok(key) => key,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(SettingsError::IoError(e.to_string())),
+        };
+        match key.delete_value(LAUNCH_NAME) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(SettingsError::IoError(e.to_string())),
+        }
+    }
+
+    pub fn is_enabled(_app: &AppHandle) -> Result<bool, SettingsError> {
+        match RegKey::predef(HKEY_CURRENT_USER).open_subkey(RUN_KEY) {
+            Ok(key) => Ok(key.get_value::<String, _>(LAUNCH_NAME).is_ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(SettingsError::IoError(e.to_string())),
+        }
+    }
+}
+
+/// launchd agent in ~/Library/LaunchAgents
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::current_exe;
+    use crate::commands::settings::SettingsError;
+    use std::fs;
+    use std::path::PathBuf;
+    use tauri::{AppHandle, Manager};
+
+    fn plist_path(app: &AppHandle) -> Result<PathBuf, SettingsError> {
+        let home = app.path().home_dir().map_err(|e| SettingsError::IoError(e.to_string()))?;
+        Ok(home
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", app.config().identifier)))
+    }
+
+    fn xml_escape(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    pub fn enable(app: &AppHandle) -> Result<(), SettingsError> {
+        let path = plist_path(app)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| SettingsError::IoError(e.to_string()))?;
+        }
+
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{program}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            label = xml_escape(&app.config().identifier),
+            program = xml_escape(&current_exe()?.display().to_string()),
+        );
+        fs::write(&path, plist).map_err(|e| SettingsError::IoError(e.to_string()))
+    }
+
+    pub fn disable(app: &AppHandle) -> Result<(), SettingsError> {
+        match fs::remove_file(plist_path(app)?) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(SettingsError::IoError(e.to_string())),
+        }
+    }
+
+    pub fn is_enabled(app: &AppHandle) -> Result<bool, SettingsError> {
+        Ok(plist_path(app)?.exists())
+    }
+}
+
+/// XDG autostart entry in ~/.config/autostart
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::{current_exe, LAUNCH_NAME};
+    use crate::commands::settings::SettingsError;
+    use std::fs;
+    use std::path::PathBuf;
+    use tauri::{AppHandle, Manager};
+
+    fn desktop_file_path(app: &AppHandle) -> Result<PathBuf, SettingsError> {
+        let config_dir = app.path().config_dir().map_err(|e| SettingsError::IoError(e.to_string()))?;
+        Ok(config_dir.join("autostart").join("relate-mail.desktop"))
+    }
+
+    pub fn enable(app: &AppHandle) -> Result<(), SettingsError> {
+        let path = desktop_file_path(app)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| SettingsError::IoError(e.to_string()))?;
+        }
+
+        // Desktop entry spec: quoted arguments escape `"`, `` ` ``, `$` and `\`
+        let exe = current_exe()?.display().to_string();
+        let mut quoted = String::with_capacity(exe.len() + 2);
+        quoted.push('"');
+        for c in exe.chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName={LAUNCH_NAME}\nExec={quoted}\nX-GNOME-Autostart-enabled=true\n"
+        );
+        fs::write(&path, entry).map_err(|e| SettingsError::IoError(e.to_string()))
+    }
+
+    pub fn disable(app: &AppHandle) -> Result<(), SettingsError> {
+        match fs::remove_file(desktop_file_path(app)?) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(SettingsError::IoError(e.to_string())),
+        }
+    }
+
+    pub fn is_enabled(app: &AppHandle) -> Result<bool, SettingsError> {
+        Ok(desktop_file_path(app)?.exists())
+    }
+}
+
+/// Register or unregister the app as an OS login item and record the choice in settings
+#[tauri::command]
+pub async fn set_auto_launch(enabled: bool, app: AppHandle) -> Result<(), SettingsError> {
+    if enabled {
+        platform::enable(&app)?;
+    } else {
+        platform::disable(&app)?;
+    }
+
+    let mut settings = get_settings_sync(&app)?;
+    settings.auto_launch = enabled;
+    write_settings(&app, &settings)
+}
+
+/// Whether the app is actually registered to launch at login, read from the OS rather
+/// than the settings file (the user may have removed it by hand)
+#[tauri::command]
+pub async fn get_auto_launch_status(app: AppHandle) -> Result<bool, SettingsError> {
+    platform::is_enabled(&app)
+}
@@ -0,0 +1,51 @@
+use crate::commands::AppState;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Copy sensitive text (an API key, an address) and clear it from the clipboard after
+/// `clear_after_secs`. Replaces any clear still pending from an earlier copy.
+#[tauri::command]
+pub async fn copy_to_clipboard_secure(
+    text: String,
+    clear_after_secs: u64,
+    app: AppHandle,
+) -> Result<(), String> {
+    app.clipboard().write_text(text.as_str()).map_err(|e| e.to_string())?;
+
+    let task_app = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(clear_after_secs)).await;
+
+        // Leave the clipboard alone if the user has since copied something else
+        let clipboard = task_app.clipboard();
+        if clipboard.read_text().is_ok_and(|current| current == text) {
+            if let Err(e) = clipboard.clear() {
+                eprintln!("Failed to clear clipboard: {e}");
+            }
+        }
+    });
+
+    let state = app.state::<AppState>();
+    let mut clear_task = state
+        .clipboard_clear_task
+        .write()
+        .map_err(|e| format!("State lock poisoned: {e}"))?;
+    if let Some(previous) = clear_task.replace(task) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+/// Keep the current clipboard contents, cancelling a pending clear
+#[tauri::command]
+pub async fn cancel_clipboard_clear(state: State<'_, AppState>) -> Result<(), String> {
+    let task = state
+        .clipboard_clear_task
+        .write()
+        .map_err(|e| format!("State lock poisoned: {e}"))?
+        .take();
+    if let Some(task) = task {
+        task.abort();
+    }
+    Ok(())
+}
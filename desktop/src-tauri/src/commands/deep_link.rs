@@ -0,0 +1,87 @@
+//! Handling for `relate://` URLs.
+//!
+//! Supported forms:
+//! - `relate://auth/callback?code=<code>&state=<state>` — OIDC redirect; emitted to the
+//!   app as an `oidc-callback` event carrying an [`OidcCallback`]. An `error` (and
+//!   optional `error_description`) query parameter is passed through instead of a code.
+//! - `relate://compose?to=<addr>[,<addr>...]` — open the composer; emitted as a
+//!   `compose-email` event carrying a [`ComposeRequest`]. `to` may also be repeated.
+//!
+//! `relate:///auth/callback` (empty host) is accepted as well.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+pub const DEEP_LINK_SCHEME: &str = "relate";
+pub const OIDC_CALLBACK_EVENT: &str = "oidc-callback";
+pub const COMPOSE_EMAIL_EVENT: &str = "compose-email";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OidcCallback {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ComposeRequest {
+    pub to: Vec<String>,
+}
+
+/// Route a `relate://` URL to the matching app event. Unknown paths are ignored.
+pub fn handle_deep_link(url: &str, app: &AppHandle) {
+    let parsed = match url::Url::parse(url) {
+        Ok(parsed) if parsed.scheme() == DEEP_LINK_SCHEME => parsed,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("Ignoring malformed deep link: {e}");
+            return;
+        }
+    };
+
+    // `relate://auth/callback` parses with "auth" as the host
+    let route = match parsed.host_str() {
+        Some(host) if !host.is_empty() => format!("/{host}{}", parsed.path()),
+        _ => parsed.path().to_string(),
+    };
+    let param = |name: &str| {
+        parsed
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    let result = if route.starts_with("/auth/callback") {
+        let error = param("error").map(|error| match param("error_description") {
+            Some(description) => format!("{error}: {description}"),
+            None => error,
+        });
+        app.emit(
+            OIDC_CALLBACK_EVENT,
+            OidcCallback {
+                code: param("code"),
+                state: param("state"),
+                error,
+            },
+        )
+    } else if route.trim_end_matches('/') == "/compose" {
+        let to = parsed
+            .query_pairs()
+            .filter(|(key, _)| key == "to")
+            .flat_map(|(_, value)| {
+                value
+                    .split(',')
+                    .map(|addr| addr.trim().to_string())
+                    .filter(|addr| !addr.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        app.emit(COMPOSE_EMAIL_EVENT, ComposeRequest { to })
+    } else {
+        return;
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to dispatch deep link: {e}");
+    }
+}
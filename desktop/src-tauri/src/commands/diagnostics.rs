@@ -0,0 +1,344 @@
+use crate::commands::audit::{self, AuditEvent};
+use crate::commands::auth::{get_api_key_for_account, load_accounts_data};
+use crate::commands::settings::get_effective_settings_sync;
+use crate::commands::updates::app_version_info;
+use crate::commands::AppState;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+const REPORT_REQUEST_COUNT: usize = 20;
+const REPORT_LOG_LINES: usize = 100;
+const REDACTED: &str = "[redacted]";
+const MAX_LATENCY_SAMPLES: u8 = 10;
+
+/// Settings that can reveal hostnames or local paths
+const SENSITIVE_SETTINGS: &[&str] = &[
+    "certificate_pins",
+    "proxy",
+    "tray_status_icons",
+    "notification_sound",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyReport {
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub mean_ms: u64,
+    pub samples: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmtpTestResult {
+    pub success: bool,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+}
+
+#[cfg(target_os = "windows")]
+const KEYRING_BACKEND: &str = "Windows Credential Manager";
+#[cfg(target_os = "macos")]
+const KEYRING_BACKEND: &str = "macOS Keychain";
+#[cfg(target_os = "linux")]
+const KEYRING_BACKEND: &str = "Linux keyutils";
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+const KEYRING_BACKEND: &str = "unknown";
+
+/// Run one collection step, turning errors and panics into a description so a
+/// single failure doesn't lose the rest of the report
+fn collect(step: impl FnOnce() -> Result<Value, String>) -> Value {
+    match catch_unwind(AssertUnwindSafe(step)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => json!({ "error": e }),
+        Err(_) => json!({ "error": "collection step panicked" }),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let release = std::fs::read_to_string("/etc/os-release").ok()?;
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    let output = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn os_version() -> Option<String> {
+    let output = std::process::Command::new("cmd")
+        .args(["/C", "ver"])
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn os_version() -> Option<String> {
+    None
+}
+
+fn collect_accounts(redact_sensitive: bool) -> Result<Value, String> {
+    let data = load_accounts_data().map_err(|e| e.to_string())?;
+    let mut report = json!({ "count": data.accounts.len() });
+
+    if !redact_sensitive {
+        let server_urls: BTreeSet<&str> = data
+            .accounts
+            .iter()
+            .map(|account| account.server_url.as_str())
+            .collect();
+        report["server_urls"] = json!(server_urls);
+    }
+
+    Ok(report)
+}
+
+fn collect_settings(app: &AppHandle, redact_sensitive: bool) -> Result<Value, String> {
+    let settings = get_effective_settings_sync(app).map_err(|e| e.to_string())?;
+    let mut value = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
+
+    if let Some(object) = value.as_object_mut() {
+        if redact_sensitive {
+            for key in SENSITIVE_SETTINGS {
+                if object.get(*key).is_some_and(|v| !v.is_null()) {
+                    object.insert((*key).to_string(), json!(REDACTED));
+                }
+            }
+        } else if let Some(proxy_url) = object
+            .get_mut("proxy")
+            .and_then(|proxy| proxy.get_mut("url"))
+        {
+            // Proxy credentials normally live in the keyring, but a URL can embed them
+            if let Some(mut url) = proxy_url.as_str().and_then(|s| url::Url::parse(s).ok()) {
+                let _ = url.set_username("");
+                let _ = url.set_password(None);
+                *proxy_url = json!(url.to_string());
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+fn collect_requests(app: &AppHandle, redact_sensitive: bool) -> Result<Value, String> {
+    let state = app.state::<AppState>();
+    let log = state
+        .request_log
+        .read()
+        .map_err(|e| format!("State lock poisoned: {e}"))?;
+
+    let records: Vec<Value> = log
+        .iter()
+        .rev()
+        .take(REPORT_REQUEST_COUNT)
+        .map(|record| {
+            let mut value = json!(record);
+            // Query strings can carry search terms and addresses
+            if redact_sensitive {
+                if let Some((path, _)) = record.endpoint.split_once('?') {
+                    value["endpoint"] = json!(format!("{path}?{REDACTED}"));
+                }
+            }
+            value
+        })
+        .collect();
+
+    Ok(json!(records))
+}
+
+/// Mask words that are URLs or email addresses
+fn redact_words(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            if word.contains('@') || word.contains("://") {
+                REDACTED
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn redact_audit_line(line: &str) -> String {
+    // A line cut short by a crash can't be redacted field by field, so drop it whole
+    let Ok(mut event) = serde_json::from_str::<AuditEvent>(line) else {
+        return REDACTED.to_string();
+    };
+    event.details = redact_words(&event.details);
+    serde_json::to_string(&event).unwrap_or_else(|_| REDACTED.to_string())
+}
+
+/// Tail of the audit log. Event details name account emails and server URLs, which are
+/// masked with `redact_sensitive`.
+fn collect_log_tail(app: &AppHandle, redact_sensitive: bool) -> Result<Value, String> {
+    let path = audit::audit_log_path(app).map_err(|e| e.to_string())?;
+    if !path.exists() {
+        return Ok(Value::Null);
+    }
+
+    let lines = read_tail(&path)?;
+    if !redact_sensitive {
+        return Ok(json!(lines));
+    }
+    let redacted: Vec<String> = lines.iter().map(|line| redact_audit_line(line)).collect();
+    Ok(json!(redacted))
+}
+
+fn read_tail(path: &Path) -> Result<Vec<String>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let lines: Vec<&str> = contents.lines().collect();
+    let start = lines.len().saturating_sub(REPORT_LOG_LINES);
+    Ok(lines[start..].iter().map(ToString::to_string).collect())
+}
+
+/// Build a JSON report for attaching to bug reports. API keys and tokens are never
+/// included; with `redact_sensitive` (the default) server URLs, hostnames and local
+/// paths are left out too.
+#[tauri::command]
+pub async fn generate_diagnostics_report(
+    redact_sensitive: Option<bool>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let redact_sensitive = redact_sensitive.unwrap_or(true);
+
+    let report = json!({
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "os": collect(|| Ok(json!({
+            "name": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "version": os_version(),
+        }))),
+        "app": collect(|| Ok(json!(app_version_info(&app)))),
+        "accounts": collect(|| collect_accounts(redact_sensitive)),
+        "settings": collect(|| collect_settings(&app, redact_sensitive)),
+        "recent_requests": collect(|| collect_requests(&app, redact_sensitive)),
+        "log_tail": collect(|| collect_log_tail(&app, redact_sensitive)),
+        "keyring_backend": KEYRING_BACKEND,
+    });
+
+    serde_json::to_string_pretty(&report).map_err(|e| e.to_string())
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Time `samples` (1-10) sequential `HEAD /api/health` requests
+#[tauri::command]
+pub async fn measure_api_latency(
+    server_url: String,
+    samples: u8,
+    state: State<'_, AppState>,
+) -> Result<LatencyReport, String> {
+    if !(1..=MAX_LATENCY_SAMPLES).contains(&samples) {
+        return Err(format!("Samples must be between 1 and {MAX_LATENCY_SAMPLES}"));
+    }
+
+    let client = state.http_client()?;
+    let url = format!("{}/api/health", server_url.trim_end_matches('/'));
+
+    let mut timings = Vec::with_capacity(usize::from(samples));
+    for _ in 0..samples {
+        let started = Instant::now();
+        client
+            .head(&url)
+            .send()
+            .await
+            .map_err(|e| format!("Health request failed: {e}"))?;
+        timings.push(elapsed_ms(started));
+    }
+
+    let total: u64 = timings.iter().sum();
+    Ok(LatencyReport {
+        min_ms: timings.iter().copied().min().unwrap_or(0),
+        max_ms: timings.iter().copied().max().unwrap_or(0),
+        mean_ms: total / u64::from(samples),
+        samples: timings,
+    })
+}
+
+/// Ask the account's server to verify its SMTP credentials. Server-side failures are
+/// reported in the result; only local problems (e.g. no stored API key) are errors.
+///
+/// The request always goes to the account's stored server, since it carries the
+/// account's API key. `server_url` must have the same origin or the call is refused.
+#[tauri::command]
+pub async fn test_smtp_connection(
+    server_url: String,
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<SmtpTestResult, String> {
+    let account = load_accounts_data()
+        .map_err(|e| e.to_string())?
+        .accounts
+        .into_iter()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| format!("Account not found: {account_id}"))?;
+
+    let origin = |url: &str| url::Url::parse(url).ok().map(|u| u.origin());
+    let account_origin = origin(&account.server_url)
+        .ok_or_else(|| "The account's server URL is invalid".to_string())?;
+    if origin(&server_url) != Some(account_origin) {
+        return Err("Server URL does not match the account's server".to_string());
+    }
+
+    let api_key = get_api_key_for_account(&account_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "No API key stored for this account".to_string())?;
+
+    let client = state.http_client()?;
+    let url = format!("{}/api/smtp-credentials/test", account.server_url.trim_end_matches('/'));
+
+    let started = Instant::now();
+    let response = client.post(&url).header("X-Api-Key", &api_key).send().await;
+    let latency_ms = elapsed_ms(started);
+
+    let error = match response {
+        Ok(resp) if resp.status().is_success() => None,
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            Some(format!("HTTP {status}: {body}"))
+        }
+        Err(e) => Some(e.to_string()),
+    };
+
+    Ok(SmtpTestResult {
+        success: error.is_none(),
+        error,
+        latency_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_words_masks_emails_and_urls() {
+        assert_eq!(
+            redact_words("user@example.com on https://mail.example.com"),
+            format!("{REDACTED} on {REDACTED}")
+        );
+        assert_eq!(redact_words("Account removed"), "Account removed");
+    }
+
+    #[test]
+    fn redact_audit_line_drops_unparseable_lines() {
+        assert_eq!(redact_audit_line("{\"timestamp\":"), REDACTED);
+    }
+}
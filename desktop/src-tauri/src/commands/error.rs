@@ -0,0 +1,62 @@
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde::Serialize;
+
+/// Outcome of a bulk operation that keeps going past individual failures. Items are
+/// identified by ID (e.g. account ID).
+#[derive(Debug)]
+pub struct MultiError<E> {
+    pub successes: Vec<String>,
+    pub failures: Vec<(String, E)>,
+}
+
+impl<E> Default for MultiError<E> {
+    fn default() -> Self {
+        Self {
+            successes: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl<E> MultiError<E> {
+    pub fn record(&mut self, id: String, result: Result<(), E>) {
+        match result {
+            Ok(()) => self.successes.push(id),
+            Err(e) => self.failures.push((id, e)),
+        }
+    }
+}
+
+/// A failure serialized as `{ id, error }`
+struct Failure<'a, E>(&'a str, &'a E);
+
+impl<E: Serialize> Serialize for Failure<'_, E> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("id", self.0)?;
+        map.serialize_entry("error", self.1)?;
+        map.end()
+    }
+}
+
+// Serialized as `{ successes: [id], failures: [{ id, error }] }`
+impl<E: Serialize> Serialize for MultiError<E> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let failures: Vec<Failure<'_, E>> = self
+            .failures
+            .iter()
+            .map(|(id, e)| Failure(id, e))
+            .collect();
+
+        let mut state = serializer.serialize_struct("MultiError", 2)?;
+        state.serialize_field("successes", &self.successes)?;
+        state.serialize_field("failures", &failures)?;
+        state.end()
+    }
+}
@@ -0,0 +1,42 @@
+use crate::commands::auth::Account;
+use crate::commands::AppState;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Tauri event carrying every `AppEvent`
+pub const APP_EVENT: &str = "app-event";
+
+/// Backend state changes pushed to the frontend, serialized as `{ type, payload }`
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", content = "payload")]
+pub enum AppEvent {
+    AccountAdded(Account),
+    AccountUpdated(Account),
+    AccountRemoved { account_id: String },
+    ActiveAccountChanged { account_id: Option<String> },
+    UnreadCountChanged { count: u32 },
+    /// The OS color scheme changed while the theme is set to follow it
+    ThemeChanged { theme: String },
+}
+
+/// Emit `event` if the frontend has subscribed; a no-op otherwise
+pub fn emit_app_event(state: &AppState, event: AppEvent) {
+    let Ok(sink) = state.event_sink.read() else {
+        return;
+    };
+    if let Some(app) = sink.as_ref() {
+        if let Err(e) = app.emit(APP_EVENT, event) {
+            eprintln!("Failed to emit app event: {e}");
+        }
+    }
+}
+
+/// Start pushing `app-event` notifications to the frontend
+#[tauri::command]
+pub async fn subscribe_to_events(app: AppHandle) {
+    let state = app.state::<AppState>();
+    match state.event_sink.write() {
+        Ok(mut sink) => *sink = Some(app.clone()),
+        Err(e) => eprintln!("Failed to subscribe to app events: {e}"),
+    };
+}
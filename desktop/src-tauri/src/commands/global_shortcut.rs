@@ -0,0 +1,52 @@
+use crate::commands::settings::{get_settings_sync, write_settings};
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// Register `shortcut` as the show-window hotkey, replacing `previous`. In-app shortcuts
+/// registered by `shortcuts` are left alone.
+pub fn apply_global_shortcut(
+    app: &AppHandle,
+    shortcut: &str,
+    previous: Option<&str>,
+) -> Result<(), String> {
+    shortcut
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut \"{shortcut}\": {e}"))?;
+
+    let manager = app.global_shortcut();
+    if let Some(previous) = previous.filter(|previous| manager.is_registered(*previous)) {
+        manager.unregister(previous).map_err(|e| e.to_string())?;
+    }
+    manager.register(shortcut).map_err(|e| {
+        format!("Could not register {shortcut}; it may already be used by another application ({e})")
+    })
+}
+
+/// Register the hotkey that shows the window, replacing any previous one, and save it
+#[tauri::command]
+pub async fn register_global_shortcut(shortcut: String, app: AppHandle) -> Result<(), String> {
+    let mut settings = get_settings_sync(&app).map_err(|e| e.to_string())?;
+
+    if let Err(e) = apply_global_shortcut(&app, &shortcut, settings.global_shortcut.as_deref()) {
+        // Put the previous hotkey back so a failed change doesn't leave none at all
+        if let Some(previous) = &settings.global_shortcut {
+            let _ = apply_global_shortcut(&app, previous, None);
+        }
+        return Err(e);
+    }
+
+    settings.global_shortcut = Some(shortcut);
+    write_settings(&app, &settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unregister_global_shortcut(app: AppHandle) -> Result<(), String> {
+    let mut settings = get_settings_sync(&app).map_err(|e| e.to_string())?;
+    if let Some(shortcut) = settings.global_shortcut.take() {
+        let manager = app.global_shortcut();
+        if manager.is_registered(shortcut.as_str()) {
+            manager.unregister(shortcut.as_str()).map_err(|e| e.to_string())?;
+        }
+    }
+    write_settings(&app, &settings).map_err(|e| e.to_string())
+}
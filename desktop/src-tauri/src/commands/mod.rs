@@ -1,13 +1,307 @@
 pub mod api;
+pub mod audit;
 pub mod auth;
+pub mod auto_launch;
+pub mod clipboard;
+pub mod deep_link;
+pub mod diagnostics;
+pub mod error;
+pub mod events;
+pub mod global_shortcut;
+pub mod notifications;
 pub mod oidc;
 pub mod settings;
+pub mod shortcuts;
+pub mod sync;
+pub mod telemetry;
+pub mod tls;
 pub mod tray;
+pub mod updates;
+pub mod window;
 
-use std::sync::RwLock;
+use api::{RateLimitInfo, RequestRecord};
+use auth::AuthError;
+use oidc::ServerDiscovery;
+use settings::ProxyConfig;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
 
+pub const DEFAULT_API_CACHE_CAPACITY: usize = 50;
+pub const DEFAULT_HTTP_TIMEOUT_SECS: u32 = 30;
+pub const DEFAULT_TCP_KEEPALIVE_SECS: u64 = 30;
+const STATE_SNAPSHOT_FILE: &str = "state.snapshot";
+/// Snapshotted discovery results older than this are dropped on restore
+const DISCOVERY_SNAPSHOT_MAX_AGE_SECS: u64 = 60 * 60;
+
+/// Cached GET responses keyed by account and endpoint, with the time they were fetched
+pub struct ApiCache(pub Mutex<LruCache<String, (String, Instant)>>);
+
+impl Default for ApiCache {
+    fn default() -> Self {
+        let capacity = NonZeroUsize::new(DEFAULT_API_CACHE_CAPACITY).unwrap_or(NonZeroUsize::MIN);
+        Self(Mutex::new(LruCache::new(capacity)))
+    }
+}
+
+/// One `reqwest::Client` shared by all commands so connections are pooled and reused.
+/// Clones of the client share the pool; it is rebuilt when its configuration changes.
+pub struct HttpClientPool {
+    client: RwLock<reqwest::Client>,
+    timeout_secs: RwLock<u64>,
+    keepalive_secs: RwLock<Option<u64>>,
+}
+
+/// Builder with the options every shared client gets. `keepalive_secs` enables
+/// `SO_KEEPALIVE` so idle pooled connections aren't silently dropped by firewalls; the
+/// value is the idle time before probing, and the OS picks the probe interval and count.
+/// TLS always goes through rustls, so handshake failures look the same with or without
+/// certificate pins.
+fn http_client_builder(
+    timeout_secs: u64,
+    keepalive_secs: Option<u64>,
+    pins: HashMap<String, String>,
+) -> Result<reqwest::ClientBuilder, String> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout_secs))
+        .tcp_keepalive(keepalive_secs.map(Duration::from_secs))
+        .connection_verbose(cfg!(debug_assertions))
+        .use_preconfigured_tls(tls::pinned_tls_config(pins)?))
+}
+
+impl Default for HttpClientPool {
+    fn default() -> Self {
+        let timeout_secs = u64::from(DEFAULT_HTTP_TIMEOUT_SECS);
+        let client =
+            http_client_builder(timeout_secs, Some(DEFAULT_TCP_KEEPALIVE_SECS), HashMap::new())
+                .and_then(|builder| builder.build().map_err(|e| e.to_string()))
+                .unwrap_or_default();
+        Self {
+            client: RwLock::new(client),
+            timeout_secs: RwLock::new(timeout_secs),
+            keepalive_secs: RwLock::new(Some(DEFAULT_TCP_KEEPALIVE_SECS)),
+        }
+    }
+}
+
+/// The parts of `AppState` persisted across restarts. The API key is deliberately
+/// left out; it is re-read from the keyring on restore.
+#[derive(Serialize, Deserialize, Default)]
+pub struct AppStateSnapshot {
+    pub server_url: Option<String>,
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub discovery: Option<DiscoverySnapshot>,
+}
+
+/// `AppState::last_discovery` with the fetch time as a Unix timestamp
+#[derive(Serialize, Deserialize)]
+pub struct DiscoverySnapshot {
+    pub server_url: String,
+    pub discovery: ServerDiscovery,
+    pub fetched_at: i64,
+}
+
+/// Shared backend state.
+///
+/// The active account's credentials are read on every API call from async commands, so
+/// they sit behind `tokio::sync::RwLock` and are awaited rather than blocking a runtime
+/// worker thread while another command holds the lock. The remaining fields are only
+/// held for short, non-async critical sections (and are also touched from sync code
+/// such as `http_client` and `settings::sync_app_state`), so they keep `std::sync`
+/// locks.
 #[derive(Default)]
 pub struct AppState {
-    pub server_url: RwLock<Option<String>>,
-    pub api_key: RwLock<Option<String>>,
+    pub server_url: Arc<tokio::sync::RwLock<Option<String>>>,
+    pub api_key: Arc<tokio::sync::RwLock<Option<String>>>,
+    pub active_account_id: Arc<tokio::sync::RwLock<Option<String>>>,
+    /// Sent as the `API-Version` header on every API request when set
+    pub api_version: Arc<tokio::sync::RwLock<Option<String>>>,
+    /// Hostname -> SHA-256 leaf certificate fingerprint, mirrored from settings
+    pub certificate_pins: RwLock<HashMap<String, String>>,
+    /// Proxy settings with credentials loaded from the keyring
+    pub proxy: RwLock<Option<ProxyConfig>>,
+    /// Rate limit headers from the most recent API response
+    pub rate_limit: RwLock<Option<RateLimitInfo>>,
+    /// Ring buffer of recent API requests for support diagnostics
+    pub request_log: RwLock<VecDeque<RequestRecord>>,
+    /// Responses cached by `api_get_cached`
+    pub api_cache: ApiCache,
+    pub http_client: HttpClientPool,
+    /// Most recent `discover_server` result: server URL, response and fetch time
+    pub last_discovery: RwLock<Option<(String, ServerDiscovery, Instant)>>,
+    /// Set once the frontend calls `subscribe_to_events`
+    pub event_sink: RwLock<Option<AppHandle>>,
+    /// Background unread-count polling started by `sync::start_background_sync`
+    pub sync_task: RwLock<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Pending clear scheduled by `clipboard::copy_to_clipboard_secure`
+    pub clipboard_clear_task: RwLock<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Watches settings.json for external edits; see `settings::start_settings_watcher`
+    pub settings_watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    /// New-mail notifications waiting for the next batch flush
+    pub notification_batcher: notifications::NotificationBatcher,
+}
+
+impl AppState {
+    /// Forget the active account and everything cached on its behalf
+    pub async fn clear(&self) -> Result<(), AuthError> {
+        let poisoned = |e: String| AuthError::Internal(format!("State lock poisoned: {e}"));
+
+        *self.server_url.write().await = None;
+        *self.api_key.write().await = None;
+        *self.active_account_id.write().await = None;
+        *self.rate_limit.write().map_err(|e| poisoned(e.to_string()))? = None;
+        self.api_cache
+            .0
+            .lock()
+            .map_err(|e| poisoned(e.to_string()))?
+            .clear();
+        Ok(())
+    }
+
+    pub async fn snapshot(&self) -> AppStateSnapshot {
+        // The discovery cache is only an optimization, so a poisoned lock just skips it
+        let discovery = self.last_discovery.read().ok().and_then(|guard| {
+            guard.as_ref().map(|(server_url, discovery, fetched)| {
+                let age = i64::try_from(fetched.elapsed().as_secs()).unwrap_or(i64::MAX);
+                DiscoverySnapshot {
+                    server_url: server_url.clone(),
+                    discovery: discovery.clone(),
+                    fetched_at: chrono::Utc::now().timestamp().saturating_sub(age),
+                }
+            })
+        });
+
+        AppStateSnapshot {
+            server_url: self.server_url.read().await.clone(),
+            account_id: self.active_account_id.read().await.clone(),
+            discovery,
+        }
+    }
+
+    /// Re-activate a snapshotted account, loading its API key from the keyring
+    pub async fn restore(&self, snap: AppStateSnapshot) -> Result<(), AuthError> {
+        let api_key = match &snap.account_id {
+            Some(account_id) => auth::get_api_key_for_account(account_id)?,
+            None => None,
+        };
+
+        *self.server_url.write().await = snap.server_url;
+        *self.api_key.write().await = api_key;
+        *self.active_account_id.write().await = snap.account_id;
+
+        // Instants can't be persisted, so rebuild one from the snapshot's age
+        let discovery = snap.discovery.and_then(|d| {
+            let age = u64::try_from(chrono::Utc::now().timestamp() - d.fetched_at).ok()?;
+            if age > DISCOVERY_SNAPSHOT_MAX_AGE_SECS {
+                return None;
+            }
+            let fetched = Instant::now().checked_sub(Duration::from_secs(age))?;
+            Some((d.server_url, d.discovery, fetched))
+        });
+        *self
+            .last_discovery
+            .write()
+            .map_err(|e| AuthError::Internal(format!("State lock poisoned: {e}")))? = discovery;
+        Ok(())
+    }
+
+    /// The shared HTTP client; cheap to clone
+    pub fn http_client(&self) -> Result<reqwest::Client, String> {
+        self.http_client
+            .client
+            .read()
+            .map(|client| client.clone())
+            .map_err(|e| format!("State lock poisoned: {e}"))
+    }
+
+    /// Replace the shared HTTP client with one using the current timeout, keep-alive,
+    /// certificate pins and proxy. Requests already in flight finish on the old client.
+    pub fn rebuild_http_client(&self) -> Result<(), String> {
+        let timeout_secs = *self
+            .http_client
+            .timeout_secs
+            .read()
+            .map_err(|e| format!("State lock poisoned: {e}"))?;
+        let keepalive_secs = *self
+            .http_client
+            .keepalive_secs
+            .read()
+            .map_err(|e| format!("State lock poisoned: {e}"))?;
+        let pins = self
+            .certificate_pins
+            .read()
+            .map_err(|e| format!("State lock poisoned: {e}"))?
+            .clone();
+        let mut builder = http_client_builder(timeout_secs, keepalive_secs, pins)?;
+
+        let proxy = self
+            .proxy
+            .read()
+            .map_err(|e| format!("State lock poisoned: {e}"))?;
+        if let Some(proxy) = proxy.as_ref() {
+            builder = builder.proxy(proxy.to_reqwest_proxy()?);
+        }
+
+        let client = builder.build().map_err(|e| e.to_string())?;
+        *self
+            .http_client
+            .client
+            .write()
+            .map_err(|e| format!("State lock poisoned: {e}"))? = client;
+        Ok(())
+    }
+
+    pub fn set_http_timeout(&self, timeout_secs: u64) -> Result<(), String> {
+        *self
+            .http_client
+            .timeout_secs
+            .write()
+            .map_err(|e| format!("State lock poisoned: {e}"))? = timeout_secs;
+        self.rebuild_http_client()
+    }
+
+    /// Set the TCP keep-alive idle time (`None` disables it). Takes effect when the
+    /// client is next rebuilt.
+    pub fn set_tcp_keepalive(&self, keepalive_secs: Option<u64>) -> Result<(), String> {
+        *self
+            .http_client
+            .keepalive_secs
+            .write()
+            .map_err(|e| format!("State lock poisoned: {e}"))? = keepalive_secs;
+        Ok(())
+    }
+}
+
+fn snapshot_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
+    Ok(app_dir.join(STATE_SNAPSHOT_FILE))
+}
+
+/// Restore state saved by `save_state_snapshot`, if any. The file is removed once
+/// restored so that a crash on the next run doesn't bring back stale state.
+pub fn restore_state_snapshot(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let path = snapshot_path(app)?;
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let snap: AppStateSnapshot = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    tauri::async_runtime::block_on(state.restore(snap)).map_err(|e| e.to_string())?;
+
+    fs::remove_file(&path).map_err(|e| e.to_string())
+}
+
+pub fn save_state_snapshot(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let snap = tauri::async_runtime::block_on(state.snapshot());
+    let json = serde_json::to_string(&snap).map_err(|e| e.to_string())?;
+    fs::write(snapshot_path(app)?, json).map_err(|e| e.to_string())
 }
@@ -0,0 +1,212 @@
+use crate::commands::settings::{
+    get_effective_settings_sync, get_settings_sync, notifications_allowed_now, write_settings,
+};
+use crate::commands::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::plugin::PermissionState;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// How often queued new-mail notifications are flushed by the background sync task
+pub const NOTIFICATION_BATCH_WINDOW: std::time::Duration = std::time::Duration::from_secs(2);
+/// Batches larger than this are collapsed into one summary notification
+const MAX_INDIVIDUAL_NOTIFICATIONS: usize = 3;
+
+/// A newly arrived email to notify about
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EmailNotification {
+    pub sender: String,
+    pub subject: String,
+    pub account_id: String,
+}
+
+/// Collects new-mail notifications so a burst of arrivals produces one summary instead
+/// of a notification per email
+#[derive(Default)]
+pub struct NotificationBatcher {
+    pending: Mutex<Vec<EmailNotification>>,
+}
+
+impl NotificationBatcher {
+    pub fn push(&self, email_summary: EmailNotification) {
+        match self.pending.lock() {
+            Ok(mut pending) => pending.push(email_summary),
+            Err(e) => eprintln!("Failed to queue notification: {e}"),
+        }
+    }
+
+    /// Show everything queued: one notification per email for small batches, otherwise
+    /// a single summary
+    pub fn flush(&self, app: &AppHandle) {
+        let batch = match self.pending.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(e) => {
+                eprintln!("Failed to flush notifications: {e}");
+                return;
+            }
+        };
+
+        let result = if batch.is_empty() {
+            Ok(())
+        } else if batch.len() <= MAX_INDIVIDUAL_NOTIFICATIONS {
+            batch
+                .into_iter()
+                .try_for_each(|email| show_notification(app, email.sender, email.subject, None))
+        } else {
+            let senders: HashSet<&str> = batch.iter().map(|e| e.sender.as_str()).collect();
+            let body = format!(
+                "{} new emails from {} sender{}",
+                batch.len(),
+                senders.len(),
+                if senders.len() == 1 { "" } else { "s" }
+            );
+            show_notification(app, "Relate Mail".to_string(), body, None)
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to show new mail notification: {e}");
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationPermission {
+    Granted,
+    Denied,
+    NotDetermined,
+}
+
+impl NotificationPermission {
+    fn as_str(self) -> &'static str {
+        match self {
+            NotificationPermission::Granted => "granted",
+            NotificationPermission::Denied => "denied",
+            NotificationPermission::NotDetermined => "not_determined",
+        }
+    }
+}
+
+impl From<PermissionState> for NotificationPermission {
+    fn from(state: PermissionState) -> Self {
+        match state {
+            PermissionState::Granted => NotificationPermission::Granted,
+            PermissionState::Denied => NotificationPermission::Denied,
+            PermissionState::Prompt | PermissionState::PromptWithRationale => {
+                NotificationPermission::NotDetermined
+            }
+        }
+    }
+}
+
+/// Remember the answer so the frontend doesn't prompt again on the next launch
+fn remember_permission(app: &AppHandle, permission: NotificationPermission) {
+    let result = get_settings_sync(app).and_then(|mut settings| {
+        if settings.notification_permission_status.as_deref() == Some(permission.as_str()) {
+            return Ok(());
+        }
+        settings.notification_permission_status = Some(permission.as_str().to_string());
+        write_settings(app, &settings)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to save notification permission: {e}");
+    }
+}
+
+#[tauri::command]
+pub async fn get_notification_permission_status(app: AppHandle) -> NotificationPermission {
+    let permission = app
+        .notification()
+        .permission_state()
+        .map_or(NotificationPermission::NotDetermined, NotificationPermission::from);
+    remember_permission(&app, permission);
+    permission
+}
+
+/// Ask the OS for notification permission, prompting the user if it hasn't been decided
+#[tauri::command]
+pub async fn request_notification_permission(app: AppHandle) -> NotificationPermission {
+    let permission = app
+        .notification()
+        .request_permission()
+        .map_or(NotificationPermission::NotDetermined, NotificationPermission::from);
+    remember_permission(&app, permission);
+    permission
+}
+
+/// The platform's stock notification sound
+fn default_sound_name() -> &'static str {
+    if cfg!(target_os = "linux") {
+        "message-new-instant"
+    } else {
+        "Default"
+    }
+}
+
+/// Show a desktop notification, applying the user's schedule, focus and sound settings.
+/// The frontend goes through this rather than the plugin so the rules apply everywhere.
+#[tauri::command]
+pub async fn send_app_notification(
+    title: String,
+    body: String,
+    sound_override: Option<String>,
+    app: AppHandle,
+) -> Result<(), String> {
+    show_notification(&app, title, body, sound_override)
+}
+
+/// Queue new-mail notifications; they are shown in batches every
+/// `NOTIFICATION_BATCH_WINDOW` by the background sync task
+#[tauri::command]
+pub async fn notify_new_emails(
+    notifications: Vec<EmailNotification>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    for notification in notifications {
+        state.notification_batcher.push(notification);
+    }
+
+    // Without the sync task nothing would flush the queue
+    let sync_running = state
+        .sync_task
+        .read()
+        .map_err(|e| format!("State lock poisoned: {e}"))?
+        .is_some();
+    if !sync_running {
+        state.notification_batcher.flush(&app);
+    }
+    Ok(())
+}
+
+fn show_notification(
+    app: &AppHandle,
+    title: String,
+    body: String,
+    sound_override: Option<String>,
+) -> Result<(), String> {
+    let settings = get_effective_settings_sync(app).map_err(|e| e.to_string())?;
+    if !notifications_allowed_now(&settings) {
+        return Ok(());
+    }
+
+    if settings.silence_when_focused {
+        let focused = app
+            .get_webview_window("main")
+            .and_then(|window| window.is_focused().ok())
+            .unwrap_or(false);
+        if focused {
+            return Ok(());
+        }
+    }
+
+    let mut notification = app.notification().builder().title(title).body(body);
+    match sound_override.or(settings.notification_sound).as_deref() {
+        Some("none") => {}
+        None | Some("default") => notification = notification.sound(default_sound_name()),
+        Some(sound) => notification = notification.sound(sound),
+    }
+
+    notification.show().map_err(|e| e.to_string())
+}
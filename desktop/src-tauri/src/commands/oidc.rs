@@ -1,13 +1,48 @@
+use crate::commands::audit::{self, AuditEventType};
+use crate::commands::auth;
+use crate::commands::deep_link::{OidcCallback, DEEP_LINK_SCHEME, OIDC_CALLBACK_EVENT};
+use crate::commands::AppState;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Listener, State};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpListener;
 
 const CALLBACK_PORT: u16 = 23847;
 const AUTH_TIMEOUT_SECS: u64 = 300; // 5 minutes
+const SILENT_AUTH_TIMEOUT_SECS: u64 = 30;
+const SILENT_AUTH_WINDOW_LABEL: &str = "oidc-silent-auth";
+/// Connections the loopback listener accepts (e.g. CORS preflights) before giving up
+const DEFAULT_CALLBACK_ATTEMPTS: u8 = 3;
+const HEALTH_CHECK_TIMEOUT_SECS: u64 = 10;
+const DISCOVERY_TIMEOUT_SECS: u64 = 10;
+/// How long `discover_server` reuses a previous result for the same server
+const DISCOVERY_CACHE_TTL_SECS: u64 = 5 * 60;
+const MAX_API_KEY_NAME_CHARS: usize = 100;
+
+/// `prompt=none` errors meaning the IdP session can't be reused without the user
+const INTERACTION_REQUIRED_ERRORS: &[&str] = &[
+    "interaction_required",
+    "login_required",
+    "consent_required",
+    "account_selection_required",
+];
+
+/// Authorization URL parameters set by the PKCE flow that callers may not override
+const RESERVED_AUTH_PARAMS: &[&str] = &[
+    "response_type",
+    "client_id",
+    "redirect_uri",
+    "scope",
+    "state",
+    "code_challenge",
+    "code_challenge_method",
+];
 
 #[derive(Debug, thiserror::Error)]
 pub enum OidcError {
@@ -21,23 +56,61 @@ pub enum OidcError {
     RequestFailed(String),
     #[error("Timeout waiting for authentication")]
     Timeout,
+    #[error("Could not resolve server address, check the server URL: {0}")]
+    DnsResolutionFailed(String),
+    #[error("Server refused the connection, it may be down: {0}")]
+    ConnectionRefused(String),
+    #[error("Sign-in required: {0}")]
+    InteractionRequired(String),
+}
+
+impl OidcError {
+    /// Whether retrying the same operation may succeed (transient or network failures),
+    /// as opposed to failures that need user or administrator action
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            OidcError::Timeout
+            | OidcError::RequestFailed(_)
+            | OidcError::DiscoveryFailed(_)
+            | OidcError::ConnectionRefused(_) => true,
+            OidcError::AuthFailed(_)
+            | OidcError::TokenExchangeFailed(_)
+            | OidcError::DnsResolutionFailed(_)
+            | OidcError::InteractionRequired(_) => false,
+        }
+    }
 }
 
+// Serialized as `{ message, retriable }` so the frontend can choose between
+// offering a retry and pointing the user at support
 impl serde::Serialize for OidcError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("OidcError", 2)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retriable", &self.is_retriable())?;
+        state.end()
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerDiscovery {
     pub discovery: serde_json::Value,
     pub oidc_config: Option<OidcConfig>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ServerHealth {
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub server_version: Option<String>,
+    pub tls_valid: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct OidcConfig {
     pub authority: String,
@@ -71,17 +144,79 @@ pub struct ApiKeyResponse {
     pub created_at: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ClientRegistrationInfo {
+    /// Computed locally; not part of the RFC 7591 response
+    #[serde(default)]
+    pub valid: bool,
+    /// Computed locally; false when the IdP couldn't be asked and the other fields only
+    /// describe what this app needs
+    #[serde(default)]
+    pub verified: bool,
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    #[serde(default)]
+    pub grant_types: Vec<String>,
+}
+
 #[derive(Deserialize)]
 struct OpenIdConfiguration {
     authorization_endpoint: String,
     token_endpoint: String,
+    #[serde(default)]
+    scopes_supported: Option<Vec<String>>,
+}
+
+fn get_client(state: &AppState) -> Result<reqwest::Client, OidcError> {
+    state.http_client().map_err(OidcError::RequestFailed)
+}
+
+/// Classify a failure to reach the server so the UI can tell a mistyped URL
+/// (DNS failure) from a server that is down (connection refused)
+fn classify_connect_error(e: &reqwest::Error) -> OidcError {
+    if e.is_connect() {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = std::error::Error::source(e);
+        while let Some(err) = source {
+            if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+                if io_err.kind() == std::io::ErrorKind::ConnectionRefused {
+                    return OidcError::ConnectionRefused(e.to_string());
+                }
+            }
+            let message = err.to_string();
+            if message.contains("dns error") || message.contains("failed to lookup address") {
+                return OidcError::DnsResolutionFailed(message);
+            }
+            source = err.source();
+        }
+    }
+
+    OidcError::DiscoveryFailed(format!("Failed to reach server: {e}"))
 }
 
-fn get_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap_or_default()
+fn has_http_scheme(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Whether the request failed in the TLS handshake (e.g. an untrusted or expired
+/// certificate, or a pin mismatch) rather than before reaching the server. Every client
+/// uses rustls (see `http_client_builder`), so that is the only TLS error to look for.
+fn is_tls_error(e: &reqwest::Error) -> bool {
+    has_rustls_error(std::error::Error::source(e))
+}
+
+fn has_rustls_error(mut source: Option<&(dyn std::error::Error + 'static)>) -> bool {
+    while let Some(err) = source {
+        // hyper-rustls wraps handshake failures in an io::Error, whose source() skips them
+        let wrapped = err
+            .downcast_ref::<std::io::Error>()
+            .and_then(std::io::Error::get_ref)
+            .and_then(|inner| inner.downcast_ref::<rustls::Error>());
+        if err.is::<rustls::Error>() || wrapped.is_some() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
 }
 
 fn generate_code_verifier() -> String {
@@ -119,51 +254,71 @@ fn parse_query_params(query: &str) -> HashMap<String, String> {
 }
 
 fn urlencoding_decode(s: &str) -> String {
-    let mut result = String::new();
-    let mut chars = s.bytes();
-    while let Some(b) = chars.next() {
-        if b == b'%' {
-            let hi = chars.next().unwrap_or(b'0');
-            let lo = chars.next().unwrap_or(b'0');
-            let hex = format!("{}{}", hi as char, lo as char);
-            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
-                result.push(byte as char);
+    // Decode to bytes first: `%C3%AB` is one UTF-8 character, not two
+    let input = s.as_bytes();
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'%' => {
+                let escaped = input
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                if let Some(byte) = escaped {
+                    bytes.push(byte);
+                    i += 3;
+                    continue;
+                }
+                // Not a valid escape; keep the '%' as written
+                bytes.push(b'%');
             }
-        } else if b == b'+' {
-            result.push(' ');
-        } else {
-            result.push(b as char);
+            b'+' => bytes.push(b' '),
+            b => bytes.push(b),
         }
+        i += 1;
     }
-    result
+    String::from_utf8_lossy(&bytes).into_owned()
 }
 
+/// Characters left unescaped: RFC 3986 "unreserved" (`A-Z a-z 0-9 - _ . ~`)
+const URL_COMPONENT: &AsciiSet =
+    &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+/// Percent-encode `s` for use in a query value or path segment. Non-ASCII characters are
+/// encoded as their UTF-8 bytes, so `"héllo"` becomes `"h%C3%A9llo"`.
 fn urlencoding_encode(s: &str) -> String {
-    let mut result = String::new();
-    for byte in s.bytes() {
-        match byte {
-            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
-                result.push(byte as char);
-            }
-            _ => {
-                result.push_str(&format!("%{byte:02X}"));
-            }
-        }
-    }
-    result
+    utf8_percent_encode(s, URL_COMPONENT).to_string()
 }
 
 #[tauri::command]
-pub async fn discover_server(server_url: String) -> Result<ServerDiscovery, OidcError> {
-    let client = get_client();
+pub async fn discover_server(
+    server_url: String,
+    timeout_secs: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<ServerDiscovery, OidcError> {
+    if !has_http_scheme(&server_url) {
+        return Err(OidcError::DiscoveryFailed("Invalid URL scheme".to_string()));
+    }
+
+    let ttl = std::time::Duration::from_secs(DISCOVERY_CACHE_TTL_SECS);
+    if let Some(cached) = cached_discovery(&state, &server_url, Some(ttl))? {
+        return Ok(cached);
+    }
+
+    let client = get_client(&state)?;
+    let timeout =
+        std::time::Duration::from_secs(timeout_secs.unwrap_or(DISCOVERY_TIMEOUT_SECS));
 
     // Fetch API discovery
     let discovery_url = format!("{server_url}/api/discovery");
     let discovery_resp = client
         .get(&discovery_url)
+        .timeout(timeout)
         .send()
         .await
-        .map_err(|e| OidcError::DiscoveryFailed(format!("Failed to reach server: {e}")))?;
+        .map_err(|e| classify_connect_error(&e))?;
 
     if !discovery_resp.status().is_success() {
         let status = discovery_resp.status();
@@ -179,7 +334,7 @@ pub async fn discover_server(server_url: String) -> Result<ServerDiscovery, Oidc
 
     // Fetch OIDC config from config.json
     let config_url = format!("{server_url}/config/config.json");
-    let oidc_config = match client.get(&config_url).send().await {
+    let oidc_config = match client.get(&config_url).timeout(timeout).send().await {
         Ok(resp) if resp.status().is_success() => {
             let config: serde_json::Value = resp
                 .json()
@@ -217,21 +372,67 @@ pub async fn discover_server(server_url: String) -> Result<ServerDiscovery, Oidc
         _ => None,
     };
 
-    Ok(ServerDiscovery {
+    let result = ServerDiscovery {
         discovery,
         oidc_config,
-    })
+    };
+    *state
+        .last_discovery
+        .write()
+        .map_err(|e| OidcError::RequestFailed(format!("State lock poisoned: {e}")))? =
+        Some((server_url, result.clone(), std::time::Instant::now()));
+
+    Ok(result)
 }
 
+/// The cached discovery result for `server_url`, if there is one no older than `max_age`
+fn cached_discovery(
+    state: &AppState,
+    server_url: &str,
+    max_age: Option<std::time::Duration>,
+) -> Result<Option<ServerDiscovery>, OidcError> {
+    let cache = state
+        .last_discovery
+        .read()
+        .map_err(|e| OidcError::RequestFailed(format!("State lock poisoned: {e}")))?;
+
+    Ok(cache
+        .as_ref()
+        .filter(|(url, _, fetched)| {
+            url == server_url && max_age.is_none_or(|max_age| fetched.elapsed() < max_age)
+        })
+        .map(|(_, discovery, _)| discovery.clone()))
+}
+
+/// The last discovery result for `server_url` regardless of age, without any network calls
 #[tauri::command]
-pub async fn start_oidc_auth(
-    authority: String,
-    client_id: String,
-    scopes: Option<String>,
-) -> Result<TokenResponse, OidcError> {
-    let client = get_client();
+pub async fn get_cached_discovery(
+    server_url: String,
+    state: State<'_, AppState>,
+) -> Result<Option<ServerDiscovery>, OidcError> {
+    cached_discovery(&state, &server_url, None)
+}
 
-    // Fetch OpenID Configuration
+/// Drop the cached discovery result for `server_url` so the next `discover_server` refetches
+#[tauri::command]
+pub async fn invalidate_discovery_cache(
+    server_url: String,
+    state: State<'_, AppState>,
+) -> Result<(), OidcError> {
+    let mut cache = state
+        .last_discovery
+        .write()
+        .map_err(|e| OidcError::RequestFailed(format!("State lock poisoned: {e}")))?;
+    if cache.as_ref().is_some_and(|(url, _, _)| *url == server_url) {
+        *cache = None;
+    }
+    Ok(())
+}
+
+async fn fetch_openid_configuration(
+    client: &reqwest::Client,
+    authority: &str,
+) -> Result<OpenIdConfiguration, OidcError> {
     let trimmed_authority = authority.trim_end_matches('/');
     let openid_config_url = format!("{trimmed_authority}/.well-known/openid-configuration");
     let openid_resp = client
@@ -240,41 +441,266 @@ pub async fn start_oidc_auth(
         .await
         .map_err(|e| OidcError::DiscoveryFailed(format!("Failed to fetch OIDC config: {e}")))?;
 
-    let openid_config: OpenIdConfiguration = openid_resp
+    openid_resp
+        .json()
+        .await
+        .map_err(|e| OidcError::DiscoveryFailed(format!("Invalid OIDC config: {e}")))
+}
+
+/// Scopes the provider advertises in `scopes_supported`; empty if it doesn't say
+#[tauri::command]
+pub async fn get_supported_scopes(
+    authority: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, OidcError> {
+    let client = get_client(&state)?;
+    let openid_config = fetch_openid_configuration(&client, &authority).await?;
+    Ok(openid_config.scopes_supported.unwrap_or_default())
+}
+
+/// Build an `OidcConfig` from a provider's `.well-known/openid-configuration` URL. The
+/// client ID comes from `client_id`, or failing that from the document's
+/// `relate_client_id` / `x-relate-client-id` extension. Nothing is saved.
+#[tauri::command]
+pub async fn import_oidc_config_from_url(
+    openid_config_url: String,
+    client_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<OidcConfig, OidcError> {
+    if !has_http_scheme(&openid_config_url) {
+        return Err(OidcError::DiscoveryFailed("Invalid URL scheme".to_string()));
+    }
+
+    let client = get_client(&state)?;
+    let resp = client
+        .get(&openid_config_url)
+        .timeout(std::time::Duration::from_secs(DISCOVERY_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(|e| classify_connect_error(&e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        return Err(OidcError::DiscoveryFailed(format!(
+            "Server returned HTTP {status}"
+        )));
+    }
+
+    let document: serde_json::Value = resp
         .json()
         .await
         .map_err(|e| OidcError::DiscoveryFailed(format!("Invalid OIDC config: {e}")))?;
+    let field = |name: &str| {
+        document
+            .get(name)
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+            .map(ToString::to_string)
+    };
 
-    // Generate PKCE parameters
-    let code_verifier = generate_code_verifier();
-    let code_challenge = generate_code_challenge(&code_verifier);
-    let state = generate_state();
+    for endpoint in ["authorization_endpoint", "token_endpoint"] {
+        if field(endpoint).is_none() {
+            return Err(OidcError::DiscoveryFailed(format!(
+                "OIDC config is missing {endpoint}"
+            )));
+        }
+    }
 
-    // Bind to fixed port only - fail loudly if unavailable
-    // Using a random port would break OIDC flows that require pre-registered redirect URIs
-    let listener = TcpListener::bind(format!("127.0.0.1:{CALLBACK_PORT}"))
+    let authority = field("issuer")
+        .ok_or_else(|| OidcError::DiscoveryFailed("OIDC config is missing issuer".to_string()))?;
+    let client_id = client_id
+        .filter(|id| !id.trim().is_empty())
+        .or_else(|| field("relate_client_id"))
+        .or_else(|| field("x-relate-client-id"))
+        .ok_or_else(|| {
+            OidcError::DiscoveryFailed(
+                "No client ID given and the OIDC config does not advertise one".to_string(),
+            )
+        })?;
+
+    Ok(OidcConfig {
+        authority,
+        client_id,
+        scopes: None,
+    })
+}
+
+/// GET `url`, returning the response and round-trip time, or the error and elapsed time
+async fn timed_get(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<(reqwest::Response, u64), (reqwest::Error, u64)> {
+    let started = std::time::Instant::now();
+    let response = client
+        .get(url)
+        .timeout(std::time::Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS))
+        .send()
+        .await;
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+    response.map(|resp| (resp, latency_ms)).map_err(|e| (e, latency_ms))
+}
+
+/// Check that a server is reachable before signing in, without authentication.
+/// Probes `/api/health`, falling back to `/api/discovery` if the server has no health endpoint.
+/// A server that fails the TLS handshake counts as reachable with `tls_valid: false`.
+#[tauri::command]
+pub async fn check_server_health(
+    server_url: String,
+    state: State<'_, AppState>,
+) -> Result<ServerHealth, String> {
+    if !has_http_scheme(&server_url) {
+        return Err("Server URL must use http or https".to_string());
+    }
+
+    let client = state.http_client()?;
+
+    let failed = |(e, latency_ms): (reqwest::Error, u64)| ServerHealth {
+        reachable: is_tls_error(&e),
+        latency_ms,
+        server_version: None,
+        tls_valid: false,
+    };
+
+    let (mut resp, mut latency_ms) =
+        match timed_get(&client, &format!("{server_url}/api/health")).await {
+            Ok(probe) => probe,
+            Err(failure) => return Ok(failed(failure)),
+        };
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        (resp, latency_ms) = match timed_get(&client, &format!("{server_url}/api/discovery")).await
+        {
+            Ok(probe) => probe,
+            Err(failure) => return Ok(failed(failure)),
+        };
+    }
+
+    let server_version = if resp.status().is_success() {
+        resp.json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|json| json.get("version").and_then(|v| v.as_str()).map(ToString::to_string))
+    } else {
+        None
+    };
+
+    // Any HTTP response over https means the TLS handshake (and any pin) was accepted
+    Ok(ServerHealth {
+        reachable: true,
+        latency_ms,
+        server_version,
+        tls_valid: server_url.starts_with("https://"),
+    })
+}
+
+/// Check a client registration with the IdP (RFC 7591 `{authority}/clients/{client_id}`)
+/// so a misconfigured `client_id` fails with a clear message instead of mid-login.
+/// Most providers don't expose the endpoint; then a synthetic result describing what
+/// this app needs is returned with `valid` and `verified` both false. Network failures
+/// are errors.
+#[tauri::command]
+pub async fn verify_client_registration(
+    authority: String,
+    client_id: String,
+    state: State<'_, AppState>,
+) -> Result<ClientRegistrationInfo, OidcError> {
+    let redirect_uri = format!("http://127.0.0.1:{CALLBACK_PORT}/auth/callback");
+    let synthetic = ClientRegistrationInfo {
+        valid: false,
+        verified: false,
+        redirect_uris: vec![redirect_uri.clone()],
+        grant_types: vec!["authorization_code".to_string()],
+    };
+
+    let client = get_client(&state)?;
+    let url = format!(
+        "{}/clients/{}",
+        authority.trim_end_matches('/'),
+        urlencoding_encode(&client_id)
+    );
+
+    let response = match client
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(DISCOVERY_TIMEOUT_SECS))
+        .send()
         .await
-        .map_err(|e| OidcError::AuthFailed(
-            format!("Port {CALLBACK_PORT} unavailable. Close other apps using this port. Error: {e}")
-        ))?;
+    {
+        Ok(response) if response.status().is_success() => response,
+        Ok(_) => return Ok(synthetic),
+        Err(e) if e.is_timeout() => return Err(OidcError::Timeout),
+        Err(e) => {
+            return Err(OidcError::RequestFailed(format!(
+                "Client registration request failed: {e}"
+            )))
+        }
+    };
 
-    let local_addr = listener
-        .local_addr()
-        .map_err(|e| OidcError::AuthFailed(format!("Failed to get listener address: {e}")))?;
+    let Ok(mut info) = response.json::<ClientRegistrationInfo>().await else {
+        return Ok(synthetic);
+    };
 
-    // Verify we got the expected port (defensive check)
-    let port = local_addr.port();
-    if port != CALLBACK_PORT {
-        return Err(OidcError::AuthFailed(
-            format!("Expected port {CALLBACK_PORT} but got {port}")
-        ));
+    // RFC 7591: grant_types defaults to authorization_code when omitted
+    if info.grant_types.is_empty() {
+        info.grant_types.push("authorization_code".to_string());
     }
+    info.verified = true;
+    info.valid = info.redirect_uris.contains(&redirect_uri)
+        && info.grant_types.iter().any(|g| g == "authorization_code");
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn start_oidc_auth(
+    authority: String,
+    client_id: String,
+    scopes: Option<String>,
+    additional_params: Option<HashMap<String, String>>,
+    use_custom_scheme: Option<bool>,
+    app: AppHandle,
+    app_state: State<'_, AppState>,
+) -> Result<TokenResponse, OidcError> {
+    // IdP-specific extras (e.g. domain_hint) must not shadow the standard parameters
+    let mut additional_params: Vec<(String, String)> =
+        additional_params.unwrap_or_default().into_iter().collect();
+    if let Some((name, _)) = additional_params
+        .iter()
+        .find(|(name, _)| RESERVED_AUTH_PARAMS.contains(&name.as_str()))
+    {
+        return Err(OidcError::AuthFailed(format!(
+            "Additional parameter '{name}' is reserved"
+        )));
+    }
+    additional_params.sort();
+
+    let client = get_client(&app_state)?;
+    let openid_config = fetch_openid_configuration(&client, &authority).await?;
+
+    // Generate PKCE parameters
+    let code_verifier = generate_code_verifier();
+    let code_challenge = generate_code_challenge(&code_verifier);
+    let state = generate_state();
 
-    let actual_redirect_uri = format!("http://127.0.0.1:{port}/auth/callback");
+    // Providers that require a registered custom scheme redirect to relate://auth/callback,
+    // which arrives as a deep link; everyone else redirects to the loopback listener
+    let (listener, actual_redirect_uri) = if use_custom_scheme.unwrap_or(false) {
+        (None, format!("{DEEP_LINK_SCHEME}://auth/callback"))
+    } else {
+        let (listener, redirect_uri) = bind_callback_listener().await?;
+        (Some(listener), redirect_uri)
+    };
 
     // Build authorization URL
     let scope = scopes.unwrap_or_else(|| "openid profile email".to_string());
-    let auth_url = format!(
+    // Providers usually ignore unknown scopes, so this is only worth a warning
+    if let Some(supported) = &openid_config.scopes_supported {
+        for requested in scope.split_whitespace() {
+            if !supported.iter().any(|s| s == requested) {
+                eprintln!("Warning: OIDC provider does not advertise scope '{requested}'");
+            }
+        }
+    }
+    let mut auth_url = format!(
         "{}?response_type=code&client_id={}&redirect_uri={}&scope={}&state={}&code_challenge={}&code_challenge_method=S256",
         openid_config.authorization_endpoint,
         urlencoding_encode(&client_id),
@@ -283,36 +709,201 @@ pub async fn start_oidc_auth(
         urlencoding_encode(&state),
         urlencoding_encode(&code_challenge),
     );
+    for (name, value) in &additional_params {
+        auth_url.push_str(&format!(
+            "&{}={}",
+            urlencoding_encode(name),
+            urlencoding_encode(value)
+        ));
+    }
+
+    // Subscribe before opening the browser so a fast redirect isn't missed
+    let (deep_link_tx, deep_link_rx) = tokio::sync::oneshot::channel::<OidcCallback>();
+    let deep_link_tx = Mutex::new(Some(deep_link_tx));
+    let deep_link_listener = app.listen(OIDC_CALLBACK_EVENT, move |event| {
+        let Ok(callback) = serde_json::from_str::<OidcCallback>(event.payload()) else {
+            return;
+        };
+        if let Some(tx) = deep_link_tx.lock().ok().and_then(|mut tx| tx.take()) {
+            let _ = tx.send(callback);
+        }
+    });
 
     // Open browser
-    open::that(&auth_url)
-        .map_err(|e| OidcError::AuthFailed(format!("Failed to open browser: {e}")))?;
+    if let Err(e) = open::that(&auth_url) {
+        app.unlisten(deep_link_listener);
+        return Err(OidcError::AuthFailed(format!("Failed to open browser: {e}")));
+    }
 
     // Wait for callback with timeout
-    let (code, received_state) = tokio::time::timeout(
-        std::time::Duration::from_secs(AUTH_TIMEOUT_SECS),
-        wait_for_callback(&listener),
+    let timeout = std::time::Duration::from_secs(AUTH_TIMEOUT_SECS);
+    let result = match &listener {
+        Some(listener) => {
+            let auth_origin = endpoint_origin(&openid_config.authorization_endpoint);
+            wait_for_multiple_callbacks(
+                listener,
+                auth_origin.as_deref(),
+                DEFAULT_CALLBACK_ATTEMPTS,
+                timeout,
+            )
+            .await
+            .and_then(|callback| loopback_code_and_state(callback).map_err(OidcError::AuthFailed))
+        }
+        None => tokio::time::timeout(timeout, wait_for_deep_link_callback(deep_link_rx))
+            .await
+            .map_err(|_| OidcError::Timeout)
+            .and_then(|result| result.map_err(OidcError::AuthFailed)),
+    };
+    app.unlisten(deep_link_listener);
+    let (code, received_state) = result?;
+
+    // Validate state
+    if received_state != state {
+        return Err(OidcError::AuthFailed("State mismatch".to_string()));
+    }
+
+    exchange_code(
+        &client,
+        &openid_config.token_endpoint,
+        &code,
+        &actual_redirect_uri,
+        &client_id,
+        &code_verifier,
     )
     .await
-    .map_err(|_| OidcError::Timeout)?
-    .map_err(OidcError::AuthFailed)?;
+}
 
-    // Validate state
+/// Re-authenticate without user interaction using `prompt=none`, relying on an existing
+/// IdP session. The authorization request runs in a hidden webview rather than the
+/// browser. Returns `InteractionRequired` when the user has to sign in again.
+///
+/// Limitation: `start_oidc_auth` signs in through the system browser, whose cookies the
+/// app's webview doesn't share, so the webview normally has no IdP session and this
+/// returns `InteractionRequired` (`login_required`). It only succeeds when the IdP
+/// session was established in the webview itself. Callers should go straight to
+/// `start_oidc_auth` rather than trying this first.
+#[tauri::command]
+pub async fn silent_oidc_reauth(
+    authority: String,
+    client_id: String,
+    scopes: Option<String>,
+    id_token_hint: Option<String>,
+    app: AppHandle,
+    app_state: State<'_, AppState>,
+) -> Result<TokenResponse, OidcError> {
+    let client = get_client(&app_state)?;
+    let openid_config = fetch_openid_configuration(&client, &authority).await?;
+
+    let code_verifier = generate_code_verifier();
+    let code_challenge = generate_code_challenge(&code_verifier);
+    let state = generate_state();
+    let (listener, redirect_uri) = bind_callback_listener().await?;
+
+    let scope = scopes.unwrap_or_else(|| "openid profile email".to_string());
+    let mut auth_url = format!(
+        "{}?response_type=code&client_id={}&redirect_uri={}&scope={}&state={}&code_challenge={}&code_challenge_method=S256&prompt=none",
+        openid_config.authorization_endpoint,
+        urlencoding_encode(&client_id),
+        urlencoding_encode(&redirect_uri),
+        urlencoding_encode(&scope),
+        urlencoding_encode(&state),
+        urlencoding_encode(&code_challenge),
+    );
+    if let Some(hint) = &id_token_hint {
+        auth_url.push_str(&format!("&id_token_hint={}", urlencoding_encode(hint)));
+    }
+
+    let url = auth_url
+        .parse()
+        .map_err(|e| OidcError::AuthFailed(format!("Invalid authorization URL: {e}")))?;
+    let window = tauri::WebviewWindowBuilder::new(
+        &app,
+        SILENT_AUTH_WINDOW_LABEL,
+        tauri::WebviewUrl::External(url),
+    )
+    .visible(false)
+    .build()
+    .map_err(|e| OidcError::AuthFailed(format!("Failed to start silent sign-in: {e}")))?;
+
+    let auth_origin = endpoint_origin(&openid_config.authorization_endpoint);
+    let result = wait_for_multiple_callbacks(
+        &listener,
+        auth_origin.as_deref(),
+        DEFAULT_CALLBACK_ATTEMPTS,
+        std::time::Duration::from_secs(SILENT_AUTH_TIMEOUT_SECS),
+    )
+    .await;
+    let _ = window.destroy();
+
+    let callback = result?;
+    if let Some(error) = callback
+        .error
+        .as_deref()
+        .filter(|error| INTERACTION_REQUIRED_ERRORS.contains(error))
+    {
+        return Err(OidcError::InteractionRequired(error.to_string()));
+    }
+
+    let (code, received_state) =
+        loopback_code_and_state(callback).map_err(OidcError::AuthFailed)?;
     if received_state != state {
         return Err(OidcError::AuthFailed("State mismatch".to_string()));
     }
 
-    // Exchange code for tokens
+    exchange_code(
+        &client,
+        &openid_config.token_endpoint,
+        &code,
+        &redirect_uri,
+        &client_id,
+        &code_verifier,
+    )
+    .await
+}
+
+/// Bind the loopback redirect listener, returning it with its redirect URI
+async fn bind_callback_listener() -> Result<(TcpListener, String), OidcError> {
+    // Bind to fixed port only - fail loudly if unavailable
+    // Using a random port would break OIDC flows that require pre-registered redirect URIs
+    let listener = TcpListener::bind(format!("127.0.0.1:{CALLBACK_PORT}"))
+        .await
+        .map_err(|e| OidcError::AuthFailed(
+            format!("Port {CALLBACK_PORT} unavailable. Close other apps using this port. Error: {e}")
+        ))?;
+
+    let local_addr = listener
+        .local_addr()
+        .map_err(|e| OidcError::AuthFailed(format!("Failed to get listener address: {e}")))?;
+
+    // Verify we got the expected port (defensive check)
+    let port = local_addr.port();
+    if port != CALLBACK_PORT {
+        return Err(OidcError::AuthFailed(
+            format!("Expected port {CALLBACK_PORT} but got {port}")
+        ));
+    }
+
+    Ok((listener, format!("http://127.0.0.1:{port}/auth/callback")))
+}
+
+async fn exchange_code(
+    client: &reqwest::Client,
+    token_endpoint: &str,
+    code: &str,
+    redirect_uri: &str,
+    client_id: &str,
+    code_verifier: &str,
+) -> Result<TokenResponse, OidcError> {
     let token_params = [
         ("grant_type", "authorization_code"),
-        ("code", &code),
-        ("redirect_uri", &actual_redirect_uri),
-        ("client_id", &client_id),
-        ("code_verifier", &code_verifier),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("client_id", client_id),
+        ("code_verifier", code_verifier),
     ];
 
     let token_resp = client
-        .post(&openid_config.token_endpoint)
+        .post(token_endpoint)
         .form(&token_params)
         .send()
         .await
@@ -334,7 +925,71 @@ pub async fn start_oidc_auth(
     Ok(tokens)
 }
 
-async fn wait_for_callback(listener: &TcpListener) -> Result<(String, String), String> {
+async fn wait_for_deep_link_callback(
+    rx: tokio::sync::oneshot::Receiver<OidcCallback>,
+) -> Result<(String, String), String> {
+    let callback = rx
+        .await
+        .map_err(|_| "Deep link callback listener closed".to_string())?;
+
+    if let Some(error) = callback.error {
+        return Err(format!("OIDC error: {error}"));
+    }
+    let code = callback.code.ok_or("No authorization code in callback")?;
+    let state = callback.state.ok_or("No state in callback")?;
+    Ok((code, state))
+}
+
+/// Origin (`scheme://host[:port]`) of an authorization server endpoint, for checking the
+/// `Origin` header on loopback callbacks
+fn endpoint_origin(endpoint: &str) -> Option<String> {
+    url::Url::parse(endpoint)
+        .ok()
+        .map(|url| url.origin().ascii_serialization())
+}
+
+/// Wait for the redirect on the loopback listener, answering CORS preflights from
+/// authorization servers that send an `OPTIONS` request to the redirect URI first. Each
+/// of the `max_attempts` connections gets an equal share of `timeout`.
+async fn wait_for_multiple_callbacks(
+    listener: &TcpListener,
+    allowed_origin: Option<&str>,
+    max_attempts: u8,
+    timeout: std::time::Duration,
+) -> Result<OidcCallback, OidcError> {
+    let max_attempts = max_attempts.max(1);
+    let attempt_timeout = timeout / u32::from(max_attempts);
+
+    for attempt in 1..=max_attempts {
+        match tokio::time::timeout(attempt_timeout, wait_for_callback(listener, allowed_origin))
+            .await
+        {
+            Ok(Ok(Some(callback))) => return Ok(callback),
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => return Err(OidcError::AuthFailed(e)),
+            // Keep listening for the rest of the overall timeout
+            Err(_) if attempt < max_attempts => {}
+            Err(_) => return Err(OidcError::Timeout),
+        }
+    }
+    Err(OidcError::AuthFailed("Too many non-code requests".to_string()))
+}
+
+/// Accept one connection on the loopback listener and answer the browser. Returns
+/// `None` for a CORS preflight, which is answered and should be followed by the redirect.
+///
+/// Requests whose `Host` isn't the listener's own address (DNS rebinding) or whose
+/// `Origin` is neither `null` nor `allowed_origin` (a web page calling `fetch()` on the
+/// port) are answered with 403 and rejected. Top-level redirects send no `Origin`.
+async fn wait_for_callback(
+    listener: &TcpListener,
+    allowed_origin: Option<&str>,
+) -> Result<Option<OidcCallback>, String> {
+    let expected_host = listener
+        .local_addr()
+        .map(|addr| format!("127.0.0.1:{}", addr.port()))
+        .map_err(|e| format!("Failed to get listener address: {e}"))?;
+
     let (mut stream, _) = listener
         .accept()
         .await
@@ -348,8 +1003,47 @@ async fn wait_for_callback(listener: &TcpListener) -> Result<(String, String), S
 
     let request = String::from_utf8_lossy(&buf[..n]).to_string();
 
+    let header = |name: &str| {
+        request
+            .lines()
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    };
+    let rejection = if header("Host") != Some(expected_host.as_str()) {
+        Some("Unexpected Host header on callback request")
+    } else {
+        match header("Origin") {
+            None | Some("null") => None,
+            Some(origin) if Some(origin) == allowed_origin => None,
+            Some(_) => Some("Callback request from an unexpected origin"),
+        }
+    };
+    if let Some(reason) = rejection {
+        let body = "<html><body><h1>Forbidden</h1></body></html>";
+        let response = format!(
+            "HTTP/1.1 403 Forbidden\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.flush().await;
+        return Err(reason.to_string());
+    }
+
     // Parse the request line to get the path and query
     let first_line = request.lines().next().unwrap_or("");
+
+    if first_line.starts_with("OPTIONS ") {
+        let allow_origin = header("Origin").unwrap_or("null");
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nAccess-Control-Allow-Origin: {allow_origin}\r\nAccess-Control-Allow-Methods: GET, OPTIONS\r\nAccess-Control-Max-Age: 600\r\nVary: Origin\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.flush().await;
+        return Ok(None);
+    }
     let path = first_line
         .split_whitespace()
         .nth(1)
@@ -388,7 +1082,11 @@ async fn wait_for_callback(listener: &TcpListener) -> Result<(String, String), S
     let _ = stream.write_all(response.as_bytes()).await;
     let _ = stream.flush().await;
 
-    match (code, state, error) {
+    Ok(Some(OidcCallback { code, state, error }))
+}
+
+fn loopback_code_and_state(callback: OidcCallback) -> Result<(String, String), String> {
+    match (callback.code, callback.state, callback.error) {
         (Some(code), Some(state), _) => Ok((code, state)),
         (_, None, _) => Err("Missing state parameter - possible CSRF attack".to_string()),
         (None, _, Some(error)) => Err(format!("OIDC error: {error}")),
@@ -396,12 +1094,35 @@ async fn wait_for_callback(listener: &TcpListener) -> Result<(String, String), S
     }
 }
 
+/// The `exp` claim of a JWT, read without verifying the signature
+pub(crate) fn token_expiry(jwt: &str) -> Result<Option<i64>, String> {
+    let payload = jwt
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| "Token is not a JWT".to_string())?;
+    let decoded = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| format!("Invalid JWT payload encoding: {e}"))?;
+    let claims: serde_json::Value =
+        serde_json::from_slice(&decoded).map_err(|e| format!("Invalid JWT payload: {e}"))?;
+
+    Ok(claims.get("exp").and_then(serde_json::Value::as_i64))
+}
+
+/// Expiry of a JWT as a Unix timestamp, for showing session time remaining. Decoded
+/// locally; no network calls and no signature check.
+#[tauri::command]
+pub async fn get_token_expiry(jwt: String) -> Result<Option<i64>, String> {
+    token_expiry(&jwt)
+}
+
 #[tauri::command]
 pub async fn fetch_profile_with_jwt(
     server_url: String,
     jwt_token: String,
+    state: State<'_, AppState>,
 ) -> Result<UserProfile, OidcError> {
-    let client = get_client();
+    let client = get_client(&state)?;
 
     let url = format!("{server_url}/api/profile");
     let resp = client
@@ -433,8 +1154,106 @@ pub async fn create_api_key_with_jwt(
     jwt_token: String,
     device_name: String,
     platform: String,
+    name_prefix: Option<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<ApiKeyResponse, OidcError> {
+    let client = get_client(&state)?;
+    let api_key_resp = request_api_key(
+        &client,
+        &server_url,
+        &jwt_token,
+        device_name,
+        &platform,
+        name_prefix.as_deref(),
+    )
+    .await?;
+
+    // No account exists yet; the key is saved to one by save_account
+    audit::record(
+        &app,
+        AuditEventType::ApiKeyCreated,
+        None,
+        format!(
+            "API key '{}' ({}) created on {server_url}",
+            api_key_resp.name, api_key_resp.id
+        ),
+    );
+
+    Ok(api_key_resp)
+}
+
+/// Replace an account's API key with a fresh one and revoke the old key. `jwt_token`
+/// must be a current access token for the account's user (e.g. from
+/// `start_oidc_auth`); tokens aren't stored by the app.
+#[tauri::command]
+pub async fn rotate_api_key(
+    account_id: String,
+    jwt_token: String,
+    device_name: String,
+    platform: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
 ) -> Result<ApiKeyResponse, OidcError> {
-    let client = get_client();
+    let account = auth::load_accounts_data()
+        .map_err(|e| OidcError::RequestFailed(e.to_string()))?
+        .accounts
+        .into_iter()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| OidcError::RequestFailed(format!("Account not found: {account_id}")))?;
+
+    let client = get_client(&state)?;
+    let new_key = request_api_key(
+        &client,
+        &account.server_url,
+        &jwt_token,
+        device_name,
+        &platform,
+        None,
+    )
+    .await?;
+
+    // Nothing is revoked unless the new key is safely stored
+    let (account, old_key_id) = auth::replace_account_api_key(&account_id, &new_key, &state)
+        .await
+        .map_err(|e| OidcError::RequestFailed(format!("Failed to store new API key: {e}")))?;
+    audit::record(
+        &app,
+        AuditEventType::ApiKeyCreated,
+        Some(account_id),
+        format!("API key '{}' ({}) replaced {old_key_id}", new_key.name, new_key.id),
+    );
+
+    // The new key already works, so a failed revoke only leaves the old one active
+    // until it is revoked on the server
+    let revoke_url = format!(
+        "{}/api/smtp-credentials/{}/revoke",
+        account.server_url,
+        urlencoding_encode(&old_key_id)
+    );
+    match client.post(&revoke_url).bearer_auth(&jwt_token).send().await {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => eprintln!("Failed to revoke old API key {old_key_id}: HTTP {}", resp.status()),
+        Err(e) => eprintln!("Failed to revoke old API key {old_key_id}: {e}"),
+    }
+
+    Ok(new_key)
+}
+
+async fn request_api_key(
+    client: &reqwest::Client,
+    server_url: &str,
+    jwt_token: &str,
+    device_name: String,
+    platform: &str,
+    name_prefix: Option<&str>,
+) -> Result<ApiKeyResponse, OidcError> {
+    // A prefix such as "Home" tells apart machines of the same model
+    let device_name = match name_prefix.map(str::trim) {
+        Some(prefix) if !prefix.is_empty() => format!("{prefix} - {device_name}"),
+        _ => device_name,
+    };
+    let device_name: String = device_name.chars().take(MAX_API_KEY_NAME_CHARS).collect();
 
     let url = format!("{server_url}/api/smtp-credentials/mobile");
     let body = serde_json::json!({
@@ -466,3 +1285,75 @@ pub async fn create_api_key_with_jwt(
 
     Ok(api_key_resp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for the hyper/reqwest layers wrapping the connector's error
+    #[derive(Debug)]
+    struct Wrapper(Box<dyn std::error::Error + Send + Sync>);
+
+    impl std::fmt::Display for Wrapper {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "wrapped: {}", self.0)
+        }
+    }
+
+    impl std::error::Error for Wrapper {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(self.0.as_ref())
+        }
+    }
+
+    fn certificate_error() -> rustls::Error {
+        rustls::Error::InvalidCertificate(rustls::CertificateError::Expired)
+    }
+
+    #[test]
+    fn has_rustls_error_finds_handshake_errors_wrapped_in_io_errors() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::InvalidData, certificate_error());
+        let chain = Wrapper(Box::new(io_error));
+        assert!(has_rustls_error(Some(&chain)));
+    }
+
+    #[test]
+    fn has_rustls_error_finds_direct_rustls_errors() {
+        let chain = Wrapper(Box::new(certificate_error()));
+        assert!(has_rustls_error(Some(&chain)));
+    }
+
+    #[test]
+    fn has_rustls_error_ignores_connection_failures() {
+        let io_error = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let chain = Wrapper(Box::new(io_error));
+        assert!(!has_rustls_error(Some(&chain)));
+        assert!(!has_rustls_error(None));
+    }
+
+    #[test]
+    fn urlencoding_decode_handles_multi_byte_utf8() {
+        assert_eq!(
+            urlencoding_decode("email=t%C3%ABst%40example.com"),
+            "email=tëst@example.com"
+        );
+    }
+
+    #[test]
+    fn urlencoding_decode_keeps_invalid_escapes() {
+        assert_eq!(urlencoding_decode("100%"), "100%");
+        assert_eq!(urlencoding_decode("a%zzb"), "a%zzb");
+        assert_eq!(urlencoding_decode("a%2"), "a%2");
+    }
+
+    #[test]
+    fn urlencoding_encode_percent_encodes_utf8_bytes() {
+        assert_eq!(urlencoding_encode("héllo"), "h%C3%A9llo");
+    }
+
+    #[test]
+    fn urlencoding_encode_leaves_unreserved_characters() {
+        assert_eq!(urlencoding_encode("AZaz09-._~"), "AZaz09-._~");
+        assert_eq!(urlencoding_encode("a b/c?d=e&f+g"), "a%20b%2Fc%3Fd%3De%26f%2Bg");
+    }
+}
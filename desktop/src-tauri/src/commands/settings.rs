@@ -1,7 +1,17 @@
+use crate::commands::auth::SERVICE_NAME;
+use crate::commands::sync;
+use crate::commands::tls::normalize_fingerprint;
+use crate::commands::{
+    AppState, DEFAULT_API_CACHE_CAPACITY, DEFAULT_HTTP_TIMEOUT_SECS, DEFAULT_TCP_KEEPALIVE_SECS,
+};
+use chrono::{Datelike, Timelike};
 use serde::{Deserialize, Serialize};
+use keyring::Entry;
+use std::collections::HashMap;
 use std::fs;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[derive(Debug, thiserror::Error)]
 pub enum SettingsError {
@@ -9,6 +19,12 @@ pub enum SettingsError {
     IoError(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Keyring error: {0}")]
+    KeyringError(String),
+    #[error("Internal error: {0}")]
+    Internal(String),
 }
 
 impl serde::Serialize for SettingsError {
@@ -20,7 +36,220 @@ impl serde::Serialize for SettingsError {
     }
 }
 
-#[derive(Serialize, Deserialize, Default)]
+const PROXY_CREDENTIALS_KEY: &str = "proxy_credentials";
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+/// Directory to keep settings.json in instead of the app config dir (portable installs, CI)
+const OVERRIDE_SETTINGS_PATH_ENV: &str = "RELATE_OVERRIDE_SETTINGS_PATH";
+/// Editors often write a file in several steps; wait this long for them to settle
+const SETTINGS_WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ProxyConfig {
+    pub url: String,
+    // Credentials live in the keyring and are never written to settings.json
+    #[serde(default, skip_serializing)]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    /// Hosts that bypass the proxy; `*` matches any run of characters (e.g. `*.internal.corp`)
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProxyCredentials {
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn to_reqwest_proxy(&self) -> Result<reqwest::Proxy, String> {
+        let proxy_url =
+            reqwest::Url::parse(&self.url).map_err(|e| format!("Invalid proxy URL: {e}"))?;
+        let no_proxy = self.no_proxy.clone();
+
+        let mut proxy = reqwest::Proxy::custom(move |url| {
+            let host = url.host_str()?;
+            if no_proxy.iter().any(|pattern| glob_matches(pattern, host)) {
+                None
+            } else {
+                Some(proxy_url.clone())
+            }
+        });
+
+        if let Some(username) = &self.username {
+            proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or(""));
+        }
+
+        Ok(proxy)
+    }
+}
+
+/// Case-insensitive hostname match where `*` matches any run of characters
+fn glob_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let host = host.to_ascii_lowercase();
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = host.strip_prefix(first) else {
+        return false;
+    };
+
+    let remaining: Vec<&str> = parts.collect();
+    let Some((last, middle)) = remaining.split_last() else {
+        // No wildcard: must be an exact match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn get_proxy_credentials_entry() -> Result<Entry, SettingsError> {
+    Entry::new(SERVICE_NAME, PROXY_CREDENTIALS_KEY)
+        .map_err(|e| SettingsError::KeyringError(e.to_string()))
+}
+
+fn load_proxy_credentials() -> Result<Option<ProxyCredentials>, SettingsError> {
+    match get_proxy_credentials_entry()?.get_password() {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| SettingsError::SerializationError(e.to_string())),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(SettingsError::KeyringError(e.to_string())),
+    }
+}
+
+/// Key that triggers the primary action in the email list. Handled by the webview, never
+/// registered as a system shortcut.
+pub const PRIMARY_ACTION_KEY: &str = "Enter";
+
+/// Action triggered by Enter in the email list
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimaryAction {
+    #[default]
+    Open,
+    Archive,
+    Delete,
+    Reply,
+}
+
+impl PrimaryAction {
+    /// Parse a `keyboard_shortcuts` action name
+    fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "open" => Some(PrimaryAction::Open),
+            "archive" => Some(PrimaryAction::Archive),
+            "delete" => Some(PrimaryAction::Delete),
+            "reply" => Some(PrimaryAction::Reply),
+            _ => None,
+        }
+    }
+}
+
+/// How the reading pane renders email bodies
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailViewer {
+    /// HTML with scripts, remote content and unsafe markup stripped
+    #[default]
+    Sanitized,
+    /// Text extracted from the body, no markup
+    Plain,
+    /// HTML exactly as received
+    Raw,
+}
+
+/// Where links clicked in emails are opened
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkOpenMode {
+    #[default]
+    DefaultBrowser,
+    /// A separate app window, so the user stays in Relate Mail
+    InAppWebview,
+}
+
+/// Hours during which notifications may be shown. Outside the window (or on a day not
+/// listed) do-not-disturb is active. `start_hour > end_hour` wraps past midnight.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NotificationSchedule {
+    pub enabled: bool,
+    /// First active hour, 0-23, inclusive
+    pub start_hour: u8,
+    /// Hour at which notifications stop, 0-23, exclusive; equal to `start_hour` means all day
+    pub end_hour: u8,
+    pub days: Vec<chrono::Weekday>,
+}
+
+impl NotificationSchedule {
+    fn is_active_at(&self, weekday: chrono::Weekday, hour: u8) -> bool {
+        if !self.days.contains(&weekday) {
+            return false;
+        }
+        if self.start_hour == self.end_hour {
+            true
+        } else if self.start_hour < self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+fn default_sound_volume() -> f32 {
+    0.7
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_thread_preview_lines() -> u8 {
+    2
+}
+
+fn default_api_cache_capacity() -> usize {
+    DEFAULT_API_CACHE_CAPACITY
+}
+
+fn default_request_timeout_secs() -> u32 {
+    DEFAULT_HTTP_TIMEOUT_SECS
+}
+
+fn default_connection_keep_alive_secs() -> Option<u64> {
+    Some(DEFAULT_TCP_KEEPALIVE_SECS)
+}
+
+fn default_font_size() -> u8 {
+    14
+}
+
+fn default_unread_poll_interval_secs() -> u64 {
+    sync::DEFAULT_SYNC_INTERVAL_SECS
+}
+
+/// Loose BCP 47 check: alphanumeric subtags of 1-8 characters joined by `-`, starting
+/// with a 2-8 letter language subtag
+fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let language_ok = subtags.next().is_some_and(|lang| {
+        (2..=8).contains(&lang.len()) && lang.chars().all(|c| c.is_ascii_alphabetic())
+    });
+    language_ok
+        && subtags.all(|sub| {
+            (1..=8).contains(&sub.len()) && sub.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct AppSettings {
     pub theme: String,
     pub minimize_to_tray: bool,
@@ -29,9 +258,241 @@ pub struct AppSettings {
     pub window_height: Option<u32>,
     pub window_x: Option<i32>,
     pub window_y: Option<i32>,
+    #[serde(default)]
+    pub reply_all_default: bool,
+    /// Server hostname -> hex SHA-256 fingerprint of the DER-encoded leaf certificate
+    #[serde(default)]
+    pub certificate_pins: HashMap<String, String>,
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub primary_action_button: PrimaryAction,
+    /// Notification sound volume, 0.0-1.0, independent of the system volume
+    #[serde(default = "default_sound_volume")]
+    pub desktop_notifications_sound_volume: f32,
+    /// Mark an email as read when its notification is dismissed without opening the app
+    #[serde(default)]
+    pub auto_mark_read_on_notification_dismiss: bool,
+    /// Maximum number of responses kept by `api_get_cached`
+    #[serde(default = "default_api_cache_capacity")]
+    pub api_cache_capacity: usize,
+    /// Snippet lines shown under each subject in the email list, 0-4; 0 hides the snippet
+    #[serde(default = "default_thread_preview_lines")]
+    pub thread_preview_lines: u8,
+    /// Tray status ("connected", "disconnected", "error", "syncing") -> custom icon path
+    #[serde(default)]
+    pub tray_status_icons: HashMap<String, String>,
+    #[serde(default)]
+    pub notification_schedule: Option<NotificationSchedule>,
+    /// Last known OS notification permission: "granted", "denied" or "not_determined"
+    #[serde(default)]
+    pub notification_permission_status: Option<String>,
+    /// Two-finger swipe actions on the email list
+    #[serde(default = "default_true")]
+    pub enable_touch_gestures: bool,
+    /// Path to a WAV/OGG file or a system sound name; "default" and "none" are special
+    #[serde(default)]
+    pub notification_sound: Option<String>,
+    /// Skip notifications entirely while the main window has focus
+    #[serde(default = "default_true")]
+    pub silence_when_focused: bool,
+    /// Launch at login; kept in step with the OS by `set_auto_launch`
+    #[serde(default)]
+    pub auto_launch: bool,
+    /// Archive the original thread once a reply to it has been sent
+    #[serde(default)]
+    pub archive_on_reply: bool,
+    /// System-wide hotkey that shows the window, e.g. "CmdOrCtrl+Shift+R"
+    #[serde(default)]
+    pub global_shortcut: Option<String>,
+    #[serde(default)]
+    pub preferred_email_viewer: EmailViewer,
+    /// Show the tray icon; when hidden the app lives only in the taskbar/dock
+    #[serde(default = "default_true")]
+    pub show_tray_icon: bool,
+    /// Timeout for HTTP requests to the server and identity provider, 5-300 seconds
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u32,
+    /// Base UI font size in points, 8-32
+    #[serde(default = "default_font_size")]
+    pub font_size: u8,
+    /// BCP 47 language tag (e.g. "en-US"); the OS locale is used when unset
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// In-app action (e.g. "compose") -> shortcut (e.g. "Ctrl+N")
+    #[serde(default)]
+    pub keyboard_shortcuts: HashMap<String, String>,
+    /// How often background sync refreshes the unread count, 10-3600 seconds
+    #[serde(default = "default_unread_poll_interval_secs")]
+    pub unread_poll_interval_secs: u64,
+    /// Keep the main window above other windows
+    #[serde(default)]
+    pub always_on_top: bool,
+    /// TCP keep-alive (`SO_KEEPALIVE`) idle time for server connections, so firewalls
+    /// don't drop them while idle; the OS sets the probe interval. `None` disables it.
+    #[serde(default = "default_connection_keep_alive_secs")]
+    pub connection_keep_alive_secs: Option<u64>,
+    #[serde(default)]
+    pub open_links_in: LinkOpenMode,
+    /// Opt-in anonymous usage statistics, sent to `telemetry_endpoint`
+    #[serde(default)]
+    pub enable_telemetry: bool,
+    #[serde(default)]
+    pub telemetry_endpoint: Option<String>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            theme: String::new(),
+            minimize_to_tray: false,
+            show_notifications: false,
+            window_width: None,
+            window_height: None,
+            window_x: None,
+            window_y: None,
+            reply_all_default: false,
+            certificate_pins: HashMap::new(),
+            proxy: None,
+            primary_action_button: PrimaryAction::default(),
+            desktop_notifications_sound_volume: default_sound_volume(),
+            auto_mark_read_on_notification_dismiss: false,
+            api_cache_capacity: default_api_cache_capacity(),
+            thread_preview_lines: default_thread_preview_lines(),
+            tray_status_icons: HashMap::new(),
+            notification_schedule: None,
+            notification_permission_status: None,
+            enable_touch_gestures: true,
+            notification_sound: None,
+            silence_when_focused: true,
+            auto_launch: false,
+            archive_on_reply: false,
+            global_shortcut: None,
+            preferred_email_viewer: EmailViewer::default(),
+            show_tray_icon: true,
+            request_timeout_secs: default_request_timeout_secs(),
+            font_size: default_font_size(),
+            locale: None,
+            keyboard_shortcuts: HashMap::new(),
+            unread_poll_interval_secs: default_unread_poll_interval_secs(),
+            always_on_top: false,
+            connection_keep_alive_secs: default_connection_keep_alive_secs(),
+            open_links_in: LinkOpenMode::default(),
+            enable_telemetry: false,
+            telemetry_endpoint: None,
+        }
+    }
+}
+
+impl AppSettings {
+    /// Action for Enter in the email list. An action bound to Enter in
+    /// `keyboard_shortcuts` wins; otherwise `primary_action_button` is the default binding.
+    pub fn primary_action(&self) -> PrimaryAction {
+        self.keyboard_shortcuts
+            .iter()
+            .filter(|(_, keys)| keys.trim().eq_ignore_ascii_case(PRIMARY_ACTION_KEY))
+            .find_map(|(action, _)| PrimaryAction::from_action(action))
+            .unwrap_or(self.primary_action_button)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), SettingsError> {
+        if !(0.0..=1.0).contains(&self.desktop_notifications_sound_volume) {
+            return Err(SettingsError::InvalidInput(
+                "Notification sound volume must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        if let Some(schedule) = &self.notification_schedule {
+            if schedule.start_hour > 23 || schedule.end_hour > 23 {
+                return Err(SettingsError::InvalidInput(
+                    "Notification schedule hours must be between 0 and 23".to_string(),
+                ));
+            }
+        }
+        if self.thread_preview_lines > 4 {
+            return Err(SettingsError::InvalidInput(
+                "Thread preview lines must be between 0 and 4".to_string(),
+            ));
+        }
+        if !(5..=300).contains(&self.request_timeout_secs) {
+            return Err(SettingsError::InvalidInput(
+                "Request timeout must be between 5 and 300 seconds".to_string(),
+            ));
+        }
+        if !(8..=32).contains(&self.font_size) {
+            return Err(SettingsError::InvalidInput(
+                "Font size must be between 8 and 32".to_string(),
+            ));
+        }
+        if let Some(locale) = &self.locale {
+            if !is_language_tag(locale) {
+                return Err(SettingsError::InvalidInput(format!(
+                    "Invalid locale '{locale}', expected a language tag such as en-US"
+                )));
+            }
+        }
+        let poll_bounds = sync::MIN_SYNC_INTERVAL_SECS..=sync::MAX_SYNC_INTERVAL_SECS;
+        if !poll_bounds.contains(&self.unread_poll_interval_secs) {
+            return Err(SettingsError::InvalidInput(format!(
+                "Unread poll interval must be between {} and {} seconds",
+                poll_bounds.start(),
+                poll_bounds.end()
+            )));
+        }
+        if self.connection_keep_alive_secs == Some(0) {
+            return Err(SettingsError::InvalidInput(
+                "Connection keep-alive must be at least 1 second; leave it unset to disable"
+                    .to_string(),
+            ));
+        }
+        if let Some(endpoint) = &self.telemetry_endpoint {
+            if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+                return Err(SettingsError::InvalidInput(
+                    "Telemetry endpoint must be an http or https URL".to_string(),
+                ));
+            }
+        }
+        for (action, shortcut) in &self.keyboard_shortcuts {
+            if action.trim().is_empty() || shortcut.trim().is_empty() {
+                return Err(SettingsError::InvalidInput(
+                    "Keyboard shortcuts need an action and a shortcut".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a notification may be shown right now, honoring the do-not-disturb schedule
+pub fn notifications_allowed_now(settings: &AppSettings) -> bool {
+    if !settings.show_notifications {
+        return false;
+    }
+
+    match &settings.notification_schedule {
+        Some(schedule) if schedule.enabled => {
+            let now = chrono::Local::now();
+            // hour() is always 0-23
+            let hour = u8::try_from(now.hour()).unwrap_or(0);
+            schedule.is_active_at(now.weekday(), hour)
+        }
+        _ => true,
+    }
+}
+
+/// Settings file location from `RELATE_OVERRIDE_SETTINGS_PATH`. Only honored for an
+/// existing directory, so a typo can't scatter settings elsewhere.
+fn override_settings_path() -> Option<PathBuf> {
+    std::env::var_os(OVERRIDE_SETTINGS_PATH_ENV)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .map(|dir| dir.join("settings.json"))
 }
 
 fn get_settings_path(app: &AppHandle) -> Result<PathBuf, SettingsError> {
+    if let Some(path) = override_settings_path() {
+        return Ok(path);
+    }
+
     let app_dir = app
         .path()
         .app_config_dir()
@@ -43,6 +504,59 @@ fn get_settings_path(app: &AppHandle) -> Result<PathBuf, SettingsError> {
     Ok(app_dir.join("settings.json"))
 }
 
+pub(crate) fn write_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), SettingsError> {
+    let path = get_settings_path(app)?;
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| SettingsError::SerializationError(e.to_string()))?;
+
+    fs::write(&path, json).map_err(|e| SettingsError::IoError(e.to_string()))
+}
+
+/// Mirror the settings that affect backend behavior (e.g. HTTP clients) into AppState
+pub fn sync_app_state(settings: &AppSettings, state: &AppState) -> Result<(), SettingsError> {
+    match state.certificate_pins.write() {
+        Ok(mut guard) => *guard = settings.certificate_pins.clone(),
+        Err(e) => return Err(SettingsError::Internal(format!("State lock poisoned: {e}"))),
+    }
+
+    let proxy = match &settings.proxy {
+        Some(config) => {
+            let mut config = config.clone();
+            if let Some(credentials) = load_proxy_credentials()? {
+                config.username = credentials.username;
+                config.password = credentials.password;
+            }
+            Some(config)
+        }
+        None => None,
+    };
+    match state.proxy.write() {
+        Ok(mut guard) => *guard = proxy,
+        Err(e) => return Err(SettingsError::Internal(format!("State lock poisoned: {e}"))),
+    }
+
+    let capacity = NonZeroUsize::new(settings.api_cache_capacity).unwrap_or(NonZeroUsize::MIN);
+    match state.api_cache.0.lock() {
+        Ok(mut guard) => guard.resize(capacity),
+        Err(e) => return Err(SettingsError::Internal(format!("State lock poisoned: {e}"))),
+    }
+
+    state
+        .set_tcp_keepalive(settings.connection_keep_alive_secs)
+        .map_err(SettingsError::Internal)?;
+    state
+        .set_http_timeout(u64::from(settings.request_timeout_secs))
+        .map_err(SettingsError::Internal)
+}
+
+fn server_hostname(server_url: &str) -> Result<String, SettingsError> {
+    reqwest::Url::parse(server_url)
+        .ok()
+        .and_then(|url| url.host_str().map(ToString::to_string))
+        .ok_or_else(|| SettingsError::InvalidInput(format!("Invalid server URL: {server_url}")))
+}
+
 /// Synchronous version for use in non-async contexts (e.g., window close handler)
 pub fn get_settings_sync(app: &AppHandle) -> Result<AppSettings, SettingsError> {
     let path = get_settings_path(app)?;
@@ -56,6 +570,137 @@ pub fn get_settings_sync(app: &AppHandle) -> Result<AppSettings, SettingsError>
     serde_json::from_str(&contents).map_err(|e| SettingsError::SerializationError(e.to_string()))
 }
 
+/// Location of the admin-managed policy file for this platform
+fn policy_path() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        let program_data =
+            std::env::var("ProgramData").unwrap_or_else(|_| r"C:\ProgramData".to_string());
+        PathBuf::from(program_data).join("Relate Mail").join("policy.json")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/Relate Mail/policy.json")
+    } else {
+        PathBuf::from("/etc/relate-mail/policy.json")
+    }
+}
+
+/// User settings with any fields set by the enterprise policy file overriding them
+pub fn get_effective_settings_sync(app: &AppHandle) -> Result<AppSettings, SettingsError> {
+    let settings = get_settings_sync(app)?;
+
+    let path = policy_path();
+    if !path.exists() {
+        return Ok(settings);
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| SettingsError::IoError(e.to_string()))?;
+    let policy: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&contents)
+        .map_err(|e| SettingsError::SerializationError(format!("Invalid policy file: {e}")))?;
+
+    let mut merged = match serde_json::to_value(&settings) {
+        Ok(serde_json::Value::Object(map)) => map,
+        Ok(_) => return Err(SettingsError::Internal("Settings are not an object".to_string())),
+        Err(e) => return Err(SettingsError::SerializationError(e.to_string())),
+    };
+    merged.extend(policy);
+
+    let effective: AppSettings = serde_json::from_value(serde_json::Value::Object(merged))
+        .map_err(|e| SettingsError::SerializationError(format!("Invalid policy file: {e}")))?;
+    effective.validate()?;
+    Ok(effective)
+}
+
+/// Watch `settings.json` for edits made outside the app, applying them to `AppState` and
+/// emitting `settings-changed` with the new effective settings. The watcher is kept in
+/// `AppState` and stopped by `stop_settings_watcher`.
+pub fn start_settings_watcher(app: &AppHandle) -> Result<(), SettingsError> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+    use std::sync::mpsc::{channel, RecvTimeoutError};
+
+    let path = get_settings_path(app)?;
+    let dir = path
+        .parent()
+        .ok_or_else(|| SettingsError::Internal("Settings path has no parent".to_string()))?
+        .to_path_buf();
+
+    let (tx, rx) = channel::<notify::Result<notify::Event>>();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| SettingsError::IoError(e.to_string()))?;
+    // Watch the directory rather than the file: editors that save by writing a temporary
+    // file and renaming it over settings.json would otherwise detach the watch
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| SettingsError::IoError(e.to_string()))?;
+
+    let is_settings_change = move |event: &notify::Result<notify::Event>| {
+        event.as_ref().is_ok_and(|event| {
+            matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+                && event.paths.iter().any(|p| p == &path)
+        })
+    };
+
+    let thread_app = app.clone();
+    let mut poll_interval = get_effective_settings_sync(app)
+        .map_or(sync::DEFAULT_SYNC_INTERVAL_SECS, |s| s.unread_poll_interval_secs);
+    // The loop ends when the watcher is dropped, which closes the channel
+    std::thread::spawn(move || {
+        while let Ok(event) = rx.recv() {
+            if !is_settings_change(&event) {
+                continue;
+            }
+            loop {
+                match rx.recv_timeout(SETTINGS_WATCH_DEBOUNCE) {
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let settings = match get_effective_settings_sync(&thread_app) {
+                Ok(settings) => settings,
+                // Likely a half-written file; the next save will trigger another event
+                Err(e) => {
+                    eprintln!("Ignoring settings change: {e}");
+                    continue;
+                }
+            };
+            if let Err(e) = sync_app_state(&settings, &thread_app.state::<AppState>()) {
+                eprintln!("Failed to apply changed settings: {e}");
+            }
+            if let Err(e) = restart_sync_on_interval_change(&thread_app, poll_interval, &settings) {
+                eprintln!("Failed to restart background sync: {e}");
+            }
+            poll_interval = settings.unread_poll_interval_secs;
+            if let Err(e) = thread_app.emit(SETTINGS_CHANGED_EVENT, &settings) {
+                eprintln!("Failed to emit {SETTINGS_CHANGED_EVENT}: {e}");
+            }
+        }
+    });
+
+    *app_state_watcher(app)? = Some(watcher);
+    Ok(())
+}
+
+fn app_state_watcher(
+    app: &AppHandle,
+) -> Result<std::sync::MutexGuard<'_, Option<notify::RecommendedWatcher>>, SettingsError> {
+    app.state::<AppState>()
+        .inner()
+        .settings_watcher
+        .lock()
+        .map_err(|e| SettingsError::Internal(format!("State lock poisoned: {e}")))
+}
+
+/// Drop the settings watcher, which also ends its event thread
+pub fn stop_settings_watcher(app: &AppHandle) -> Result<(), SettingsError> {
+    app_state_watcher(app)?.take();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_effective_settings(app: AppHandle) -> Result<AppSettings, SettingsError> {
+    get_effective_settings_sync(&app)
+}
+
 #[tauri::command]
 pub async fn get_settings(app: AppHandle) -> Result<AppSettings, SettingsError> {
     let path = get_settings_path(&app)?;
@@ -69,14 +714,350 @@ pub async fn get_settings(app: AppHandle) -> Result<AppSettings, SettingsError>
     serde_json::from_str(&contents).map_err(|e| SettingsError::SerializationError(e.to_string()))
 }
 
+/// Location of `settings.json`, for users who want to back it up by hand
 #[tauri::command]
-pub async fn save_settings(settings: AppSettings, app: AppHandle) -> Result<(), SettingsError> {
-    let path = get_settings_path(&app)?;
+pub async fn get_settings_file_path(app: AppHandle) -> Result<String, SettingsError> {
+    Ok(get_settings_path(&app)?.to_string_lossy().into_owned())
+}
 
-    let json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| SettingsError::SerializationError(e.to_string()))?;
+/// Directory holding the settings, state snapshot and audit log, for "Reveal in
+/// Finder/Explorer"
+#[tauri::command]
+pub async fn get_app_config_dir(app: AppHandle) -> Result<String, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+/// Restart background sync when the effective poll interval differs from `previous_secs`
+fn restart_sync_on_interval_change(
+    app: &AppHandle,
+    previous_secs: u64,
+    effective: &AppSettings,
+) -> Result<(), SettingsError> {
+    if effective.unread_poll_interval_secs == previous_secs {
+        return Ok(());
+    }
+    sync::start_background_sync(app, effective.unread_poll_interval_secs)
+        .map_err(SettingsError::Internal)
+}
+
+#[tauri::command]
+pub async fn save_settings(
+    settings: AppSettings,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), SettingsError> {
+    settings.validate()?;
+    let previous = get_settings_sync(&app)?;
+    let previous_interval = get_effective_settings_sync(&app)?.unread_poll_interval_secs;
+    // Only the user's own choices are persisted; policy is re-applied on every read
+    write_settings(&app, &settings)?;
+    let effective = get_effective_settings_sync(&app)?;
+    sync_app_state(&effective, &state)?;
+
+    if previous.enable_touch_gestures != settings.enable_touch_gestures {
+        apply_touch_gestures(&app)?;
+    }
+    restart_sync_on_interval_change(&app, previous_interval, &effective)?;
+
+    Ok(())
+}
+
+/// Restore default settings, optionally keeping the window's size and position. The
+/// active account lives with the accounts, not in settings, so it is unaffected.
+#[tauri::command]
+pub async fn reset_settings_to_defaults(
+    preserve_window_geometry: bool,
+    app: AppHandle,
+) -> Result<AppSettings, SettingsError> {
+    let previous = get_settings_sync(&app)?;
+    let previous_interval = get_effective_settings_sync(&app)?.unread_poll_interval_secs;
+    let mut settings = AppSettings::default();
+    if preserve_window_geometry {
+        settings.window_width = previous.window_width;
+        settings.window_height = previous.window_height;
+        settings.window_x = previous.window_x;
+        settings.window_y = previous.window_y;
+    }
+
+    write_settings(&app, &settings)?;
+    let effective = get_effective_settings_sync(&app)?;
+    sync_app_state(&effective, &app.state::<AppState>())?;
+
+    if previous.enable_touch_gestures != settings.enable_touch_gestures {
+        apply_touch_gestures(&app)?;
+    }
+    restart_sync_on_interval_change(&app, previous_interval, &effective)?;
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_archive_on_reply(app: AppHandle) -> Result<bool, SettingsError> {
+    Ok(get_effective_settings_sync(&app)?.archive_on_reply)
+}
+
+/// The configured locale, or the OS locale when none is set
+#[tauri::command]
+pub async fn get_locale(app: AppHandle) -> String {
+    get_effective_settings_sync(&app)
+        .ok()
+        .and_then(|settings| settings.locale)
+        .or_else(sys_locale::get_locale)
+        .unwrap_or_else(|| "en-US".to_string())
+}
+
+#[tauri::command]
+pub async fn get_email_viewer(app: AppHandle) -> Result<EmailViewer, SettingsError> {
+    Ok(get_effective_settings_sync(&app)?.preferred_email_viewer)
+}
+
+#[tauri::command]
+pub async fn get_primary_action(app: AppHandle) -> Result<PrimaryAction, SettingsError> {
+    Ok(get_effective_settings_sync(&app)?.primary_action())
+}
+
+/// `"light"` or `"dark"`: the configured theme, or for `"system"` (and the unset
+/// default) the OS color scheme reported for the main window
+pub fn resolve_theme(app: &AppHandle, os_theme: Option<tauri::Theme>) -> String {
+    let configured = get_effective_settings_sync(app)
+        .map(|s| s.theme)
+        .unwrap_or_default();
+    if configured == "light" || configured == "dark" {
+        return configured;
+    }
+
+    let os_theme = os_theme.or_else(|| {
+        app.get_webview_window("main")
+            .and_then(|window| window.theme().ok())
+    });
+    match os_theme {
+        Some(tauri::Theme::Dark) => "dark".to_string(),
+        // Platforms that can't report a scheme fall back to light
+        _ => "light".to_string(),
+    }
+}
+
+/// Whether the theme follows the OS, so OS theme changes need to reach the frontend
+pub fn follows_system_theme(app: &AppHandle) -> bool {
+    get_effective_settings_sync(app)
+        .map(|s| s.theme != "light" && s.theme != "dark")
+        .unwrap_or(true)
+}
+
+#[tauri::command]
+pub async fn get_effective_theme(app: AppHandle) -> String {
+    resolve_theme(&app, None)
+}
+
+/// Checked by the frontend before firing a desktop notification
+#[tauri::command]
+pub async fn should_show_notification(app: AppHandle) -> bool {
+    // If settings can't be read, err on the side of not dropping notifications
+    get_effective_settings_sync(&app)
+        .map(|settings| notifications_allowed_now(&settings))
+        .unwrap_or(true)
+}
+
+/// Tell the frontend's gesture listener whether to handle swipes. The flag is also
+/// announced with a `relate:touch-gestures` event for listeners already attached.
+fn apply_touch_gestures(app: &AppHandle) -> Result<(), SettingsError> {
+    let enabled = get_effective_settings_sync(app)?.enable_touch_gestures;
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+
+    let script = format!(
+        "window.__RELATE_TOUCH_GESTURES__ = {enabled}; \
+         window.dispatchEvent(new CustomEvent('relate:touch-gestures', {{ detail: {enabled} }}));"
+    );
+    window
+        .eval(&script)
+        .map_err(|e| SettingsError::Internal(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn configure_touch_gestures(app: AppHandle) -> Result<(), SettingsError> {
+    apply_touch_gestures(&app)
+}
+
+#[tauri::command]
+pub async fn get_reply_all_default(app: AppHandle) -> Result<bool, SettingsError> {
+    Ok(get_settings_sync(&app)?.reply_all_default)
+}
+
+/// Pin the TLS leaf certificate for a server, identified by its SHA-256 fingerprint
+#[tauri::command]
+pub async fn add_certificate_pin(
+    server_url: String,
+    fingerprint: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), SettingsError> {
+    let host = server_hostname(&server_url)?;
+    let fingerprint = normalize_fingerprint(&fingerprint).ok_or_else(|| {
+        SettingsError::InvalidInput("Fingerprint must be a SHA-256 hex digest".to_string())
+    })?;
+
+    let mut settings = get_settings_sync(&app)?;
+    settings.certificate_pins.insert(host, fingerprint);
+    write_settings(&app, &settings)?;
+    sync_app_state(&settings, &state)?;
 
-    fs::write(&path, json).map_err(|e| SettingsError::IoError(e.to_string()))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_certificate_pin(
+    server_url: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), SettingsError> {
+    let host = server_hostname(&server_url)?;
+
+    let mut settings = get_settings_sync(&app)?;
+    settings.certificate_pins.remove(&host);
+    write_settings(&app, &settings)?;
+    sync_app_state(&settings, &state)?;
+
+    Ok(())
+}
+
+/// Configure the HTTP proxy; credentials are stored in the keyring, not settings.json
+#[tauri::command]
+pub async fn set_proxy_config(
+    config: ProxyConfig,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), SettingsError> {
+    config.to_reqwest_proxy().map_err(SettingsError::InvalidInput)?;
+
+    let entry = get_proxy_credentials_entry()?;
+    if config.username.is_some() || config.password.is_some() {
+        let credentials = ProxyCredentials {
+            username: config.username.clone(),
+            password: config.password.clone(),
+        };
+        let json = serde_json::to_string(&credentials)
+            .map_err(|e| SettingsError::SerializationError(e.to_string()))?;
+        entry
+            .set_password(&json)
+            .map_err(|e| SettingsError::KeyringError(e.to_string()))?;
+    } else {
+        // Ignore error if entry doesn't exist
+        let _ = entry.delete_credential();
+    }
+
+    let mut settings = get_settings_sync(&app)?;
+    settings.proxy = Some(config);
+    write_settings(&app, &settings)?;
+    sync_app_state(&settings, &state)?;
 
     Ok(())
 }
+
+#[tauri::command]
+pub async fn clear_proxy_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), SettingsError> {
+    // Ignore error if entry doesn't exist
+    let _ = get_proxy_credentials_entry()?.delete_credential();
+
+    let mut settings = get_settings_sync(&app)?;
+    settings.proxy = None;
+    write_settings(&app, &settings)?;
+    sync_app_state(&settings, &state)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday::{Fri, Mon, Sat, Sun, Thu, Tue, Wed};
+
+    fn schedule(start_hour: u8, end_hour: u8) -> NotificationSchedule {
+        NotificationSchedule {
+            enabled: true,
+            start_hour,
+            end_hour,
+            days: vec![Mon, Tue, Wed, Thu, Fri],
+        }
+    }
+
+    #[test]
+    fn override_settings_path_uses_existing_directory() {
+        let dir = std::env::temp_dir().join(format!("relate-settings-{}", std::process::id()));
+        assert!(std::fs::create_dir_all(&dir).is_ok());
+
+        std::env::set_var(OVERRIDE_SETTINGS_PATH_ENV, &dir);
+        assert_eq!(override_settings_path(), Some(dir.join("settings.json")));
+
+        std::env::set_var(OVERRIDE_SETTINGS_PATH_ENV, dir.join("missing"));
+        assert_eq!(override_settings_path(), None);
+
+        std::env::remove_var(OVERRIDE_SETTINGS_PATH_ENV);
+        assert_eq!(override_settings_path(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn primary_action_defaults_to_primary_action_button() {
+        let settings = AppSettings {
+            primary_action_button: PrimaryAction::Archive,
+            ..AppSettings::default()
+        };
+        assert_eq!(settings.primary_action(), PrimaryAction::Archive);
+    }
+
+    #[test]
+    fn primary_action_prefers_an_enter_binding() {
+        let mut settings = AppSettings::default();
+        settings
+            .keyboard_shortcuts
+            .insert("reply".to_string(), "Enter".to_string());
+        settings
+            .keyboard_shortcuts
+            .insert("compose".to_string(), "Ctrl+N".to_string());
+        assert_eq!(settings.primary_action(), PrimaryAction::Reply);
+    }
+
+    #[test]
+    fn schedule_start_hour_is_inclusive_and_end_hour_exclusive() {
+        let schedule = schedule(9, 17);
+        assert!(!schedule.is_active_at(Mon, 8));
+        assert!(schedule.is_active_at(Mon, 9));
+        assert!(schedule.is_active_at(Mon, 16));
+        assert!(!schedule.is_active_at(Mon, 17));
+    }
+
+    #[test]
+    fn schedule_wraps_past_midnight() {
+        let schedule = schedule(22, 6);
+        assert!(!schedule.is_active_at(Tue, 21));
+        assert!(schedule.is_active_at(Tue, 22));
+        assert!(schedule.is_active_at(Tue, 23));
+        assert!(schedule.is_active_at(Tue, 0));
+        assert!(schedule.is_active_at(Tue, 5));
+        assert!(!schedule.is_active_at(Tue, 6));
+        assert!(!schedule.is_active_at(Tue, 12));
+    }
+
+    #[test]
+    fn schedule_with_equal_hours_is_active_all_day() {
+        let schedule = schedule(8, 8);
+        for hour in 0..24 {
+            assert!(schedule.is_active_at(Wed, hour));
+        }
+    }
+
+    #[test]
+    fn schedule_excludes_unlisted_days() {
+        let schedule = schedule(0, 0);
+        assert!(schedule.is_active_at(Fri, 12));
+        assert!(!schedule.is_active_at(Sat, 12));
+        assert!(!schedule.is_active_at(Sun, 12));
+    }
+}
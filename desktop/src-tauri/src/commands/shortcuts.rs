@@ -0,0 +1,134 @@
+use crate::commands::settings::{get_settings_sync, write_settings, PRIMARY_ACTION_KEY};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// Emitted with the action name (e.g. "compose") when an in-app shortcut is pressed
+pub const APP_SHORTCUT_EVENT: &str = "app-shortcut";
+
+/// The configured action bound to `shortcut`, if any. Anything else registered with the
+/// plugin is the show-window hotkey.
+pub fn shortcut_action(app: &AppHandle, shortcut: &Shortcut) -> Option<String> {
+    let settings = get_settings_sync(app).ok()?;
+    settings
+        .keyboard_shortcuts
+        .into_iter()
+        .find(|(_, keys)| keys.parse::<Shortcut>().is_ok_and(|keys| &keys == shortcut))
+        .map(|(action, _)| action)
+}
+
+pub fn emit_shortcut_action(app: &AppHandle, action: &str) {
+    if let Err(e) = app.emit(APP_SHORTCUT_EVENT, action) {
+        eprintln!("Failed to emit shortcut action {action}: {e}");
+    }
+}
+
+/// In-app shortcuts are only registered while the main window has focus
+fn main_window_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false)
+}
+
+/// Enter in the email list is handled by the webview (see `AppSettings::primary_action`)
+fn is_primary_action_key(shortcut: &str) -> bool {
+    shortcut.trim().eq_ignore_ascii_case(PRIMARY_ACTION_KEY)
+}
+
+fn register_shortcut(app: &AppHandle, action: &str, shortcut: &str) -> Result<(), String> {
+    let parsed = shortcut
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut \"{shortcut}\" for {action}: {e}"))?;
+
+    let manager = app.global_shortcut();
+    if manager.is_registered(parsed) {
+        return Ok(());
+    }
+    manager.register(parsed).map_err(|e| {
+        format!("Could not register {shortcut} for {action}; it may already be in use ({e})")
+    })
+}
+
+/// Register every shortcut from settings. All are attempted; the failures are reported
+/// together. Called when the main window gains focus.
+pub fn apply_app_shortcuts(app: &AppHandle) -> Result<(), String> {
+    let settings = get_settings_sync(app).map_err(|e| e.to_string())?;
+    let errors: Vec<String> = settings
+        .keyboard_shortcuts
+        .iter()
+        .filter(|(_, shortcut)| !is_primary_action_key(shortcut))
+        .filter_map(|(action, shortcut)| register_shortcut(app, action, shortcut).err())
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Unregister the shortcuts from settings so other apps get the keys back, leaving the
+/// show-window hotkey in place. Called when the main window loses focus.
+pub fn release_app_shortcuts(app: &AppHandle) -> Result<(), String> {
+    let settings = get_settings_sync(app).map_err(|e| e.to_string())?;
+    let manager = app.global_shortcut();
+    let errors: Vec<String> = settings
+        .keyboard_shortcuts
+        .values()
+        .filter(|shortcut| settings.global_shortcut.as_ref() != Some(*shortcut))
+        .filter(|shortcut| manager.is_registered(shortcut.as_str()))
+        .filter_map(|shortcut| manager.unregister(shortcut.as_str()).err())
+        .map(|e| e.to_string())
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+#[tauri::command]
+pub async fn register_app_shortcuts(app: AppHandle) -> Result<(), String> {
+    if !main_window_focused(&app) {
+        return Ok(());
+    }
+    apply_app_shortcuts(&app)
+}
+
+/// Bind `action` to `shortcut`, replacing its previous shortcut, and save it
+#[tauri::command]
+pub async fn update_shortcut(
+    action: String,
+    shortcut: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut settings = get_settings_sync(&app).map_err(|e| e.to_string())?;
+    let manager = app.global_shortcut();
+
+    let previous = settings.keyboard_shortcuts.get(&action).cloned();
+    // The show-window hotkey may share keys with the old binding; leave it registered
+    let unregister_previous = previous
+        .as_deref()
+        .filter(|previous| settings.global_shortcut.as_deref() != Some(*previous))
+        .filter(|previous| manager.is_registered(*previous));
+    if let Some(previous) = unregister_previous {
+        manager.unregister(previous).map_err(|e| e.to_string())?;
+    }
+
+    // Enter belongs to the email list, and while unfocused nothing is registered (the new
+    // binding is picked up on focus); either way the shortcut only needs to be saved
+    if is_primary_action_key(&shortcut) || !main_window_focused(&app) {
+        shortcut
+            .parse::<Shortcut>()
+            .map_err(|e| format!("Invalid shortcut \"{shortcut}\" for {action}: {e}"))?;
+    } else if let Err(e) = register_shortcut(&app, &action, &shortcut) {
+        // Restore the old binding so a failed change doesn't leave the action unbound
+        if let Some(previous) = unregister_previous {
+            let _ = register_shortcut(&app, &action, previous);
+        }
+        return Err(e);
+    }
+
+    settings.keyboard_shortcuts.insert(action, shortcut);
+    write_settings(&app, &settings).map_err(|e| e.to_string())
+}
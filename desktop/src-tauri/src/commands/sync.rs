@@ -0,0 +1,128 @@
+use crate::commands::events::{emit_app_event, AppEvent};
+use crate::commands::notifications::NOTIFICATION_BATCH_WINDOW;
+use crate::commands::settings;
+use crate::commands::tray::apply_badge_count;
+use crate::commands::AppState;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+
+pub const DEFAULT_SYNC_INTERVAL_SECS: u64 = 60;
+pub const MIN_SYNC_INTERVAL_SECS: u64 = 10;
+pub const MAX_SYNC_INTERVAL_SECS: u64 = 3600;
+
+#[derive(Deserialize)]
+struct UnreadCount {
+    count: u32,
+}
+
+/// Unread count for the active account, or `None` when no account is active
+async fn fetch_unread_count(state: &AppState) -> Result<Option<u32>, String> {
+    let Some(server_url) = state.server_url.read().await.clone() else {
+        return Ok(None);
+    };
+    let Some(api_key) = state.api_key.read().await.clone() else {
+        return Ok(None);
+    };
+
+    let response = state
+        .http_client()?
+        .get(format!("{server_url}/api/unread-count"))
+        .header("X-Api-Key", &api_key)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+
+    let unread: UnreadCount = response.json().await.map_err(|e| e.to_string())?;
+    Ok(Some(unread.count))
+}
+
+/// Poll the unread count every `interval_secs`, updating the badge and emitting
+/// `UnreadCountChanged` when it changes. Replaces any sync task already running.
+pub fn start_background_sync(app: &AppHandle, interval_secs: u64) -> Result<(), String> {
+    let task_app = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let state = task_app.state::<AppState>();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        let mut flush_interval = tokio::time::interval(NOTIFICATION_BATCH_WINDOW);
+        let mut last_count = None;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = flush_interval.tick() => {
+                    state.notification_batcher.flush(&task_app);
+                    continue;
+                }
+            }
+
+            let count = match fetch_unread_count(&state).await {
+                Ok(Some(count)) => count,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Background sync failed: {e}");
+                    continue;
+                }
+            };
+            if last_count == Some(count) {
+                continue;
+            }
+            last_count = Some(count);
+
+            if let Err(e) = apply_badge_count(&task_app, count) {
+                eprintln!("Failed to update badge count: {e}");
+            }
+            emit_app_event(&state, AppEvent::UnreadCountChanged { count });
+        }
+    });
+
+    let state = app.state::<AppState>();
+    let mut sync_task = state
+        .sync_task
+        .write()
+        .map_err(|e| format!("State lock poisoned: {e}"))?;
+    if let Some(previous) = sync_task.replace(task) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+/// Restart background sync with the interval from settings
+#[tauri::command]
+pub async fn restart_background_sync(app: AppHandle) -> Result<(), String> {
+    let interval = settings::get_effective_settings_sync(&app)
+        .map_err(|e| e.to_string())?
+        .unread_poll_interval_secs;
+    start_background_sync(&app, interval)
+}
+
+/// Save a new polling interval to settings and restart background sync with the
+/// effective interval (a policy-enforced value still wins)
+#[tauri::command]
+pub async fn set_poll_interval(secs: u64, app: AppHandle) -> Result<(), String> {
+    let mut user_settings = settings::get_settings_sync(&app).map_err(|e| e.to_string())?;
+    user_settings.unread_poll_interval_secs = secs;
+    user_settings.validate().map_err(|e| e.to_string())?;
+    settings::write_settings(&app, &user_settings).map_err(|e| e.to_string())?;
+
+    let interval = settings::get_effective_settings_sync(&app)
+        .map_err(|e| e.to_string())?
+        .unread_poll_interval_secs;
+    start_background_sync(&app, interval)
+}
+
+#[tauri::command]
+pub async fn stop_background_sync(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let task = state
+        .sync_task
+        .write()
+        .map_err(|e| format!("State lock poisoned: {e}"))?
+        .take();
+    if let Some(task) = task {
+        task.abort();
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use crate::commands::settings::get_effective_settings_sync;
+use crate::commands::AppState;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Payload keys containing any of these are dropped before an event is sent
+const BLOCKED_PAYLOAD_KEYS: &[&str] = &["email", "api_key", "server_url", "token"];
+
+/// An anonymous usage event. Built with `TelemetryEvent::new`, which fills in the app
+/// version and OS.
+#[derive(Serialize, Clone, Debug)]
+pub struct TelemetryEvent {
+    pub event_type: String,
+    pub app_version: String,
+    pub os: String,
+    pub payload: serde_json::Value,
+}
+
+impl TelemetryEvent {
+    pub fn new(app: &AppHandle, event_type: &str, payload: serde_json::Value) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            app_version: app.package_info().version.to_string(),
+            os: std::env::consts::OS.to_string(),
+            payload,
+        }
+    }
+}
+
+/// Recursively remove object keys that could identify the user or their server, e.g.
+/// `email`, `user_email`, `api_key`, `server_url`, `access_token`
+pub fn sanitize_telemetry_payload(v: serde_json::Value) -> serde_json::Value {
+    match v {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .filter(|(key, _)| {
+                let key = key.to_lowercase();
+                !BLOCKED_PAYLOAD_KEYS.iter().any(|blocked| key.contains(blocked))
+            })
+            .map(|(key, value)| (key, sanitize_telemetry_payload(value)))
+            .collect(),
+        serde_json::Value::Array(items) => {
+            items.into_iter().map(sanitize_telemetry_payload).collect()
+        }
+        other => other,
+    }
+}
+
+/// Send `event` to the configured telemetry endpoint if the user has opted in. Runs in
+/// the background; failures are only logged.
+pub fn maybe_report_event(app: &AppHandle, event: TelemetryEvent) {
+    let Ok(settings) = get_effective_settings_sync(app) else {
+        return;
+    };
+    let Some(endpoint) = settings.telemetry_endpoint.filter(|_| settings.enable_telemetry) else {
+        return;
+    };
+    let Ok(client) = app.state::<AppState>().http_client() else {
+        return;
+    };
+
+    let event = TelemetryEvent {
+        payload: sanitize_telemetry_payload(event.payload),
+        ..event
+    };
+    tauri::async_runtime::spawn(async move {
+        let result = client
+            .post(&endpoint)
+            .json(&event)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        if let Err(e) = result {
+            eprintln!("Failed to report telemetry event {}: {e}", event.event_type);
+        }
+    });
+}
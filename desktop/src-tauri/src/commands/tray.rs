@@ -1,34 +1,110 @@
+use crate::commands::auth::{self, Account};
+use crate::commands::settings;
+use crate::commands::window::show_main_window;
+use crate::commands::AppState;
+use serde::Deserialize;
 use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder},
+    image::Image,
+    menu::{Menu, MenuBuilder, MenuItemBuilder},
     tray::TrayIconBuilder,
-    AppHandle, Manager,
+    AppHandle, Emitter, Manager, Wry,
 };
 
-pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+const ACCOUNT_ITEM_PREFIX: &str = "account_";
+
+/// Connection state reflected by the tray icon
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayStatus {
+    Connected,
+    Disconnected,
+    Error,
+    Syncing,
+}
+
+impl TrayStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            TrayStatus::Connected => "connected",
+            TrayStatus::Disconnected => "disconnected",
+            TrayStatus::Error => "error",
+            TrayStatus::Syncing => "syncing",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TrayError {
+    #[error("System tray is unavailable: {0}")]
+    Unsupported(String),
+    #[error("Failed to build tray menu: {0}")]
+    Menu(String),
+}
+
+/// "Show", one entry per account for quick switching, then "Quit"
+fn build_tray_menu(app: &AppHandle, accounts: &[Account]) -> tauri::Result<Menu<Wry>> {
     let show = MenuItemBuilder::with_id("show", "Show Relate Mail").build(app)?;
     let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
 
-    let menu = MenuBuilder::new(app)
-        .item(&show)
-        .separator()
-        .item(&quit)
-        .build()?;
+    let mut builder = MenuBuilder::new(app).item(&show);
+    for account in accounts {
+        let item = MenuItemBuilder::with_id(
+            format!("{ACCOUNT_ITEM_PREFIX}{}", account.id),
+            format!("{} – {}", account.display_name, account.user_email),
+        )
+        .build(app)?;
+        builder = builder.item(&item);
+    }
+
+    builder.separator().item(&quit).build()
+}
+
+/// Regenerate the tray menu from the stored accounts
+pub fn rebuild_tray_menu(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(tray) = app.tray_by_id("main") else {
+        return Ok(());
+    };
+
+    let data = auth::load_accounts_data()?;
+    let menu = build_tray_menu(app, &data.accounts)?;
+    tray.set_menu(Some(menu))?;
+    Ok(())
+}
+
+fn switch_account(app: &AppHandle, account_id: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        match auth::activate_account(&account_id, &state).await {
+            // Let the frontend reload its account state and mailbox
+            Ok(account) => {
+                let _ = app.emit("active-account-changed", account);
+            }
+            Err(e) => eprintln!("Failed to switch account from tray: {e}"),
+        }
+    });
+}
+
+pub fn create_tray(app: &AppHandle) -> Result<(), TrayError> {
+    // A missing or unreadable account list shouldn't prevent the tray from appearing
+    let accounts = auth::load_accounts_data()
+        .map(|data| data.accounts)
+        .unwrap_or_default();
+    let menu = build_tray_menu(app, &accounts).map_err(|e| TrayError::Menu(e.to_string()))?;
 
     let _tray = TrayIconBuilder::with_id("main")
         .menu(&menu)
         .tooltip("Relate Mail")
         .on_menu_event(move |app, event| match event.id().as_ref() {
-            "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                    let _ = window.unminimize();
-                }
-            }
+            "show" => show_main_window(app),
             "quit" => {
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(account_id) = id.strip_prefix(ACCOUNT_ITEM_PREFIX) {
+                    switch_account(app, account_id.to_string());
+                }
+            }
         })
         .on_tray_icon_event(|tray, event| {
             if let tauri::tray::TrayIconEvent::Click {
@@ -37,19 +113,71 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                 ..
             } = event
             {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                    let _ = window.unminimize();
-                }
+                show_main_window(tray.app_handle());
             }
         })
-        .build(app)?;
+        .build(app)
+        // Minimal window managers and some Wayland compositors have no tray to attach to
+        .map_err(|e| TrayError::Unsupported(e.to_string()))?;
 
     Ok(())
 }
 
+/// Swap the tray icon for the one matching `status`, preferring a user override from
+/// settings over the bundled `icons/tray-<status>.png`
+pub fn apply_tray_status(app: &AppHandle, status: TrayStatus) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id("main") else {
+        return Ok(());
+    };
+
+    let custom = settings::get_settings_sync(app)
+        .ok()
+        .and_then(|s| s.tray_status_icons.get(status.as_str()).cloned());
+    let path = match custom {
+        Some(path) => std::path::PathBuf::from(path),
+        None => app
+            .path()
+            .resource_dir()
+            .map_err(|e| e.to_string())?
+            .join("icons")
+            .join(format!("tray-{}.png", status.as_str())),
+    };
+
+    let icon = Image::from_path(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    tray.set_icon(Some(icon)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_tray_status(status: TrayStatus, app: AppHandle) -> Result<(), String> {
+    apply_tray_status(&app, status)
+}
+
+#[tauri::command]
+pub async fn refresh_tray_menu(app: AppHandle) -> Result<(), String> {
+    rebuild_tray_menu(&app).map_err(|e| e.to_string())
+}
+
+/// Show or hide the tray icon and remember the choice for the next launch
+fn set_tray_visible(app: &AppHandle, visible: bool) -> Result<(), String> {
+    if let Some(tray) = app.tray_by_id("main") {
+        tray.set_visible(visible).map_err(|e| e.to_string())?;
+    }
+
+    let mut settings = settings::get_settings_sync(app).map_err(|e| e.to_string())?;
+    settings.show_tray_icon = visible;
+    settings::write_settings(app, &settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_tray_icon(app: AppHandle) -> Result<(), String> {
+    set_tray_visible(&app, false)
+}
+
+#[tauri::command]
+pub async fn restore_tray_icon(app: AppHandle) -> Result<(), String> {
+    set_tray_visible(&app, true)
+}
+
 #[tauri::command]
 pub async fn set_tray_tooltip(tooltip: String, app: AppHandle) -> Result<(), String> {
     if let Some(tray) = app.tray_by_id("main") {
@@ -58,8 +186,52 @@ pub async fn set_tray_tooltip(tooltip: String, app: AppHandle) -> Result<(), Str
     Ok(())
 }
 
+/// Replace the tray icon with a PNG supplied at runtime (e.g. a server-provided logo)
+#[tauri::command]
+pub async fn set_tray_icon_from_bytes(png_bytes: Vec<u8>, app: AppHandle) -> Result<(), String> {
+    match image::guess_format(&png_bytes) {
+        Ok(image::ImageFormat::Png) => {}
+        _ => return Err("Icon data is not a valid PNG image".to_string()),
+    }
+
+    let icon = Image::from_bytes(&png_bytes).map_err(|e| e.to_string())?;
+
+    if let Some(tray) = app.tray_by_id("main") {
+        tray.set_icon(Some(icon)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Red dot shown over the taskbar button while there is unread mail
+#[cfg(target_os = "windows")]
+fn unread_overlay_icon() -> Image<'static> {
+    const SIZE: u32 = 16;
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    let radius = SIZE as f32 / 2.0;
+
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let dx = x as f32 - center;
+            let dy = y as f32 - center;
+            if dx * dx + dy * dy <= radius * radius {
+                rgba.extend_from_slice(&[220, 38, 38, 255]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+    }
+    Image::new_owned(rgba, SIZE, SIZE)
+}
+
+/// Show the unread count on the tray tooltip and window title, plus the Dock badge on
+/// macOS and a taskbar overlay on Windows. Linux only gets the tooltip.
 #[tauri::command]
 pub async fn set_badge_count(count: u32, app: AppHandle) -> Result<(), String> {
+    apply_badge_count(&app, count)
+}
+
+pub fn apply_badge_count(app: &AppHandle, count: u32) -> Result<(), String> {
     if let Some(tray) = app.tray_by_id("main") {
         let tooltip = if count > 0 {
             format!("Relate Mail - {count} unread")
@@ -77,6 +249,18 @@ pub async fn set_badge_count(count: u32, app: AppHandle) -> Result<(), String> {
             "Relate Mail".to_string()
         };
         window.set_title(&title).map_err(|e| e.to_string())?;
+
+        // Sets NSApp.dockTile.badgeLabel; None clears the badge
+        #[cfg(target_os = "macos")]
+        window
+            .set_badge_count((count > 0).then_some(i64::from(count)))
+            .map_err(|e| e.to_string())?;
+
+        // Overlay icons can't carry text, so Windows just flags that there is unread mail
+        #[cfg(target_os = "windows")]
+        window
+            .set_overlay_icon((count > 0).then(unread_overlay_icon))
+            .map_err(|e| e.to_string())?;
     }
 
     Ok(())
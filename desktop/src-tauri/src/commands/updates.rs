@@ -0,0 +1,91 @@
+use crate::commands::api::get_client;
+use crate::commands::AppState;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppVersionInfo {
+    pub version: String,
+    pub build_date: String,
+    pub git_commit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub current: String,
+    pub latest: String,
+    pub download_url: String,
+    pub release_notes: String,
+    pub update_available: bool,
+}
+
+/// Latest release as described by the GitHub releases API; the aliases accept
+/// simpler custom endpoints
+#[derive(Debug, Deserialize)]
+struct ReleaseResponse {
+    #[serde(alias = "version")]
+    tag_name: String,
+    #[serde(alias = "download_url", alias = "url")]
+    html_url: String,
+    #[serde(default, alias = "release_notes", alias = "notes")]
+    body: Option<String>,
+}
+
+/// Parse a version, tolerating the `v` prefix common on release tags
+fn parse_version(version: &str) -> Result<semver::Version, String> {
+    let trimmed = version.trim();
+    semver::Version::parse(trimmed.strip_prefix('v').unwrap_or(trimmed))
+        .map_err(|e| format!("Invalid version '{version}': {e}"))
+}
+
+pub(crate) fn app_version_info(app: &AppHandle) -> AppVersionInfo {
+    AppVersionInfo {
+        version: app.package_info().version.to_string(),
+        build_date: env!("RELATE_BUILD_DATE").to_string(),
+        git_commit: option_env!("RELATE_GIT_COMMIT").map(str::to_string),
+    }
+}
+
+#[tauri::command]
+pub async fn get_app_version(app: AppHandle) -> AppVersionInfo {
+    app_version_info(&app)
+}
+
+/// Compare the running version against the latest release published at `release_url`
+#[tauri::command]
+pub async fn check_for_update(
+    release_url: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<UpdateInfo, String> {
+    let client = get_client(&state).map_err(|e| e.to_string())?;
+
+    let response = client
+        .get(&release_url)
+        // GitHub's API rejects requests without a User-Agent
+        .header("User-Agent", "RelateMailDesktop")
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("Update check failed: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Update check failed: HTTP {}", response.status()));
+    }
+
+    let release: ReleaseResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid release response: {e}"))?;
+
+    let current = app.package_info().version.clone();
+    let latest = parse_version(&release.tag_name)?;
+
+    Ok(UpdateInfo {
+        current: current.to_string(),
+        latest: latest.to_string(),
+        download_url: release.html_url,
+        release_notes: release.body.unwrap_or_default(),
+        update_available: latest > current,
+    })
+}
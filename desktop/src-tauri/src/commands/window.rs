@@ -0,0 +1,81 @@
+use crate::commands::settings::{
+    get_effective_settings_sync, get_settings_sync, write_settings, LinkOpenMode,
+};
+use tauri::{AppHandle, Manager};
+
+const LINK_WINDOW_LABEL: &str = "external-link";
+
+/// Bring the main window to the front, restoring it from the tray or minimized state
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.unminimize();
+    }
+}
+
+/// Whether the main window has input focus; false if it can't be found
+#[tauri::command]
+pub async fn is_window_focused(app: AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false)
+}
+
+/// Whether the main window is shown (not hidden to the tray); false if it can't be found
+#[tauri::command]
+pub async fn is_window_visible(app: AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|window| window.is_visible().ok())
+        .unwrap_or(false)
+}
+
+/// Float the main window above other windows and remember the choice. Platforms
+/// without support (e.g. some Wayland compositors) only log a warning.
+#[tauri::command]
+pub async fn set_window_always_on_top(enabled: bool, app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("main") {
+        if let Err(e) = window.set_always_on_top(enabled) {
+            eprintln!("Warning: could not change always-on-top: {e}");
+        }
+    }
+
+    let mut settings = get_settings_sync(&app).map_err(|e| e.to_string())?;
+    settings.always_on_top = enabled;
+    write_settings(&app, &settings).map_err(|e| e.to_string())
+}
+
+/// Open a link from an email in the browser or an in-app window, per `open_links_in`.
+/// Only http(s) URLs are accepted, so `javascript:` and `data:` links are refused.
+/// The in-app window shows remote content, which tauri 2.12+ keeps from invoking app
+/// commands since no capability grants them to remote origins.
+#[tauri::command]
+pub async fn open_external_url(url: String, app: AppHandle) -> Result<(), String> {
+    let parsed = tauri::Url::parse(&url).map_err(|e| format!("Invalid URL: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Refusing to open {} URL", parsed.scheme()));
+    }
+
+    let mode = get_effective_settings_sync(&app)
+        .map_err(|e| e.to_string())?
+        .open_links_in;
+    match mode {
+        LinkOpenMode::DefaultBrowser => open::that(&url).map_err(|e| e.to_string()),
+        // Reuse one window rather than piling up a window per click
+        LinkOpenMode::InAppWebview => match app.get_webview_window(LINK_WINDOW_LABEL) {
+            Some(window) => {
+                window.navigate(parsed).map_err(|e| e.to_string())?;
+                window.set_focus().map_err(|e| e.to_string())
+            }
+            None => tauri::WebviewWindowBuilder::new(
+                &app,
+                LINK_WINDOW_LABEL,
+                tauri::WebviewUrl::External(parsed),
+            )
+            .title("Relate Mail")
+            .build()
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        },
+    }
+}
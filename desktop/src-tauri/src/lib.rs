@@ -8,24 +8,157 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, shortcut, event| {
+                    if event.state() != tauri_plugin_global_shortcut::ShortcutState::Pressed {
+                        return;
+                    }
+                    // In-app shortcuts map to an action; anything else is "show the window"
+                    match commands::shortcuts::shortcut_action(app, shortcut) {
+                        Some(action) => commands::shortcuts::emit_shortcut_action(app, &action),
+                        None => commands::window::show_main_window(app),
+                    }
+                })
+                .build(),
+        )
         .setup(|app| {
             // Initialize app state
-            app.manage(commands::AppState::default());
+            let state = commands::AppState::default();
+            match commands::settings::get_effective_settings_sync(app.handle()) {
+                Ok(settings) => {
+                    if let Err(e) = commands::settings::sync_app_state(&settings, &state) {
+                        eprintln!("Failed to apply settings: {e}");
+                    }
+                    if let Some(shortcut) = &settings.global_shortcut {
+                        let result = commands::global_shortcut::apply_global_shortcut(
+                            app.handle(),
+                            shortcut,
+                            None,
+                        );
+                        if let Err(e) = result {
+                            eprintln!("Failed to register global shortcut: {e}");
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to load settings: {e}"),
+            }
+            if let Err(e) = commands::restore_state_snapshot(app.handle(), &state) {
+                eprintln!("Failed to restore state snapshot: {e}");
+            }
+            app.manage(state);
+
+            let event = commands::telemetry::TelemetryEvent::new(
+                app.handle(),
+                "app_started",
+                serde_json::json!({}),
+            );
+            commands::telemetry::maybe_report_event(app.handle(), event);
+
+            if let Err(e) = commands::settings::start_settings_watcher(app.handle()) {
+                eprintln!("Failed to watch settings file: {e}");
+            }
+
+            // Keep the badge current even while the window (and its JS polling) is hidden
+            let interval = commands::settings::get_effective_settings_sync(app.handle())
+                .map(|s| s.unread_poll_interval_secs)
+                .unwrap_or(commands::sync::DEFAULT_SYNC_INTERVAL_SECS);
+            if let Err(e) = commands::sync::start_background_sync(app.handle(), interval) {
+                eprintln!("Failed to start background sync: {e}");
+            }
+
+            // relate:// links (OIDC callbacks, compose actions)
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+
+                // Linux and Windows installers don't always register the scheme (and dev
+                // builds never do), so register it at runtime
+                #[cfg(any(target_os = "linux", target_os = "windows"))]
+                if let Err(e) = app.deep_link().register_all() {
+                    eprintln!("Failed to register relate:// URL scheme: {e}");
+                }
+
+                let app_handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    for url in event.urls() {
+                        commands::deep_link::handle_deep_link(url.as_str(), &app_handle);
+                    }
+                });
+            }
 
             // Create system tray
-            if let Err(e) = commands::tray::create_tray(app.handle()) {
-                eprintln!("Failed to create tray: {e}");
+            match commands::tray::create_tray(app.handle()) {
+                // Nothing has reached the server yet
+                Ok(()) => {
+                    let status = commands::tray::TrayStatus::Disconnected;
+                    if let Err(e) = commands::tray::apply_tray_status(app.handle(), status) {
+                        eprintln!("Failed to set tray status icon: {e}");
+                    }
+
+                    let show_tray_icon =
+                        commands::settings::get_effective_settings_sync(app.handle())
+                            .map(|s| s.show_tray_icon)
+                            .unwrap_or(true);
+                    if !show_tray_icon {
+                        if let Some(tray) = app.tray_by_id("main") {
+                            if let Err(e) = tray.set_visible(false) {
+                                eprintln!("Failed to hide tray icon: {e}");
+                            }
+                        }
+                    }
+                }
+                Err(e @ commands::tray::TrayError::Unsupported(_)) => {
+                    eprintln!("Warning: {e}; minimize to tray is disabled");
+                }
+                Err(e) => eprintln!("Failed to create tray: {e}"),
             }
 
             // Handle window close event - minimize to tray instead of quitting
             let app_handle = app.handle().clone();
             if let Some(window) = app.get_webview_window("main") {
+                let always_on_top = commands::settings::get_effective_settings_sync(app.handle())
+                    .map(|s| s.always_on_top)
+                    .unwrap_or(false);
+                if always_on_top {
+                    if let Err(e) = window.set_always_on_top(true) {
+                        eprintln!("Warning: could not keep window on top: {e}");
+                    }
+                }
+
                 window.on_window_event(move |event| {
+                    if let tauri::WindowEvent::ThemeChanged(theme) = event {
+                        if commands::settings::follows_system_theme(&app_handle) {
+                            let theme =
+                                commands::settings::resolve_theme(&app_handle, Some(*theme));
+                            let state = app_handle.state::<commands::AppState>();
+                            commands::events::emit_app_event(
+                                &state,
+                                commands::events::AppEvent::ThemeChanged { theme },
+                            );
+                        }
+                    }
+                    if let tauri::WindowEvent::Focused(focused) = event {
+                        // In-app shortcuts are system-wide keys, so hold them only while
+                        // the window has focus
+                        let result = if *focused {
+                            commands::shortcuts::apply_app_shortcuts(&app_handle)
+                        } else {
+                            commands::shortcuts::release_app_shortcuts(&app_handle)
+                        };
+                        if let Err(e) = result {
+                            eprintln!("Failed to update keyboard shortcuts: {e}");
+                        }
+                    }
                     if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                        // Check settings for minimize_to_tray preference
-                        let should_minimize = commands::settings::get_settings_sync(&app_handle)
-                            .map(|s| s.minimize_to_tray)
-                            .unwrap_or(false);
+                        // Check settings for minimize_to_tray preference; without a visible
+                        // tray there would be no way to bring the window back, so just close
+                        let has_tray = app_handle.tray_by_id("main").is_some();
+                        let should_minimize = has_tray
+                            && commands::settings::get_effective_settings_sync(&app_handle)
+                                .map(|s| s.minimize_to_tray && s.show_tray_icon)
+                                .unwrap_or(false);
 
                         if should_minimize {
                             // Hide window instead of closing
@@ -43,10 +176,28 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             commands::api::api_get,
+            commands::api::api_get_stream,
+            commands::api::api_get_cached,
             commands::api::api_post,
+            commands::api::api_post_multipart_json,
             commands::api::api_put,
             commands::api::api_patch,
             commands::api::api_delete,
+            commands::api::api_delete_with_body,
+            commands::api::get_server_capabilities,
+            commands::api::warm_connection,
+            commands::api::get_rate_limit_info,
+            commands::api::get_app_state_summary,
+            commands::api::set_api_version_header,
+            #[cfg(debug_assertions)]
+            commands::api::set_server_url,
+            #[cfg(debug_assertions)]
+            commands::api::set_api_key,
+            commands::api::reconfigure_http_client,
+            commands::api::api_batch_post,
+            commands::api::api_get_paginated,
+            commands::api::api_batch,
+            commands::api::get_last_request_ids,
             commands::auth::save_credentials,
             commands::auth::load_credentials,
             commands::auth::clear_credentials,
@@ -54,17 +205,107 @@ pub fn run() {
             commands::auth::get_account_api_key,
             commands::auth::save_account,
             commands::auth::delete_account,
+            commands::auth::delete_accounts,
             commands::auth::set_active_account,
+            commands::auth::is_account_active,
+            commands::auth::get_active_account,
+            commands::auth::get_api_key_metadata,
+            commands::auth::get_all_api_keys,
+            #[cfg(debug_assertions)]
+            commands::auth::list_keyring_account_ids,
+            commands::auth::cleanup_orphaned_api_keys,
             commands::auth::generate_account_id,
+            commands::auth::validate_account,
+            commands::auth::validate_all_accounts,
+            commands::auth::update_account_metadata,
+            commands::auth::update_account_scopes,
+            commands::auth::get_account_by_email,
+            commands::auth::get_accounts_by_email,
+            commands::auth::filter_accounts_by_group,
+            commands::auth::set_account_groups,
+            commands::auth::derive_account_color,
+            commands::auth::get_recently_used_accounts,
+            commands::auth::export_accounts,
+            commands::auth::import_accounts,
+            commands::auth::rotate_account_api_key_with_password,
+            commands::auth::reset_app_state,
+            commands::auth::rotate_all_account_ids,
             commands::settings::get_settings,
             commands::settings::save_settings,
+            commands::settings::reset_settings_to_defaults,
+            commands::settings::get_effective_settings,
+            commands::settings::get_settings_file_path,
+            commands::settings::get_app_config_dir,
+            commands::settings::get_reply_all_default,
+            commands::settings::get_archive_on_reply,
+            commands::settings::get_email_viewer,
+            commands::settings::get_primary_action,
+            commands::settings::get_effective_theme,
+            commands::settings::get_locale,
+            commands::settings::should_show_notification,
+            commands::settings::configure_touch_gestures,
+            commands::auto_launch::set_auto_launch,
+            commands::auto_launch::get_auto_launch_status,
+            commands::settings::add_certificate_pin,
+            commands::settings::remove_certificate_pin,
+            commands::settings::set_proxy_config,
+            commands::settings::clear_proxy_config,
+            commands::notifications::get_notification_permission_status,
+            commands::notifications::request_notification_permission,
+            commands::notifications::send_app_notification,
+            commands::notifications::notify_new_emails,
+            commands::tray::refresh_tray_menu,
+            commands::tray::set_tray_status,
             commands::tray::set_tray_tooltip,
+            commands::tray::remove_tray_icon,
+            commands::tray::restore_tray_icon,
             commands::tray::set_badge_count,
+            commands::tray::set_tray_icon_from_bytes,
+            commands::window::is_window_focused,
+            commands::global_shortcut::register_global_shortcut,
+            commands::global_shortcut::unregister_global_shortcut,
+            commands::shortcuts::register_app_shortcuts,
+            commands::shortcuts::update_shortcut,
+            commands::window::is_window_visible,
+            commands::window::set_window_always_on_top,
+            commands::window::open_external_url,
+            commands::clipboard::copy_to_clipboard_secure,
+            commands::clipboard::cancel_clipboard_clear,
             commands::oidc::discover_server,
+            commands::oidc::get_cached_discovery,
+            commands::oidc::invalidate_discovery_cache,
+            commands::oidc::import_oidc_config_from_url,
+            commands::oidc::check_server_health,
+            commands::oidc::verify_client_registration,
+            commands::oidc::get_supported_scopes,
             commands::oidc::start_oidc_auth,
+            commands::oidc::silent_oidc_reauth,
+            commands::oidc::get_token_expiry,
             commands::oidc::fetch_profile_with_jwt,
             commands::oidc::create_api_key_with_jwt,
+            commands::oidc::rotate_api_key,
+            commands::updates::get_app_version,
+            commands::updates::check_for_update,
+            commands::events::subscribe_to_events,
+            commands::audit::get_audit_log,
+            commands::sync::set_poll_interval,
+            commands::sync::restart_background_sync,
+            commands::sync::stop_background_sync,
+            commands::diagnostics::generate_diagnostics_report,
+            commands::diagnostics::measure_api_latency,
+            commands::diagnostics::test_smtp_connection,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = commands::settings::stop_settings_watcher(app_handle) {
+                    eprintln!("Failed to stop settings watcher: {e}");
+                }
+                let state = app_handle.state::<commands::AppState>();
+                if let Err(e) = commands::save_state_snapshot(app_handle, &state) {
+                    eprintln!("Failed to save state snapshot: {e}");
+                }
+            }
+        });
 }
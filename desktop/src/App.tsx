@@ -7,6 +7,8 @@ import { Settings } from './views/Settings'
 import { SmtpSettings } from './views/SmtpSettings'
 import { Login } from './views/Login'
 import { useTheme } from './hooks/useTheme'
+import { useDisplayPreferences } from './hooks/useDisplayPreferences'
+import { useAppEvents } from './hooks/useAppEvents'
 import { usePolling } from './hooks/usePolling'
 import { useSignalR } from './hooks/useSignalR'
 import { useWindowState } from './hooks/useWindowState'
@@ -31,6 +33,8 @@ function App() {
 
   // Initialize theme (follows system by default)
   useTheme()
+  useDisplayPreferences()
+  useAppEvents()
 
   // Load accounts on mount
   useEffect(() => {
@@ -7,11 +7,24 @@ async function delay(ms: number): Promise<void> {
   return new Promise(resolve => { setTimeout(resolve, ms) })
 }
 
+type TrayStatus = 'connected' | 'disconnected' | 'error' | 'syncing'
+
+// The tray starts out 'disconnected'; only tell the backend when the status changes
+let trayStatus: TrayStatus = 'disconnected'
+
+function reportTrayStatus(status: TrayStatus) {
+  if (status === trayStatus) return
+  trayStatus = status
+  invoke('set_tray_status', { status }).catch(() => {})
+}
+
 async function withRetry<T>(fn: () => Promise<T>, retries = MAX_RETRIES): Promise<T> {
   let lastError: Error | undefined
   for (let i = 0; i < retries; i++) {
     try {
-      return await fn()
+      const result = await fn()
+      reportTrayStatus('connected')
+      return result
     } catch (error) {
       lastError = error instanceof Error ? error : new Error(String(error))
       if (i < retries - 1) {
@@ -19,6 +32,7 @@ async function withRetry<T>(fn: () => Promise<T>, retries = MAX_RETRIES): Promis
       }
     }
   }
+  reportTrayStatus('error')
   throw lastError ?? new Error('Unknown error after retries')
 }
 
@@ -0,0 +1,41 @@
+import { useEffect } from 'react'
+import { useSetAtom } from 'jotai'
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { loadAccountsAtom } from '../stores/accounts'
+
+export type AppEvent =
+  | { type: 'AccountAdded'; payload: unknown }
+  | { type: 'AccountUpdated'; payload: unknown }
+  | { type: 'AccountRemoved'; payload: { account_id: string } }
+  | { type: 'ActiveAccountChanged'; payload: { account_id: string | null } }
+  | { type: 'UnreadCountChanged'; payload: { count: number } }
+  | { type: 'ThemeChanged'; payload: { theme: 'light' | 'dark' } }
+
+// Keep account state in step with changes made by the backend (e.g. from the tray)
+export function useAppEvents() {
+  const loadAccounts = useSetAtom(loadAccountsAtom)
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined
+    let active = true
+
+    listen<AppEvent>('app-event', (event) => {
+      if (event.payload.type.startsWith('Account') || event.payload.type === 'ActiveAccountChanged') {
+        loadAccounts().catch(() => {})
+      }
+    }).then((fn) => {
+      if (active) {
+        unlisten = fn
+        invoke('subscribe_to_events').catch(() => {})
+      } else {
+        fn()
+      }
+    })
+
+    return () => {
+      active = false
+      unlisten?.()
+    }
+  }, [loadAccounts])
+}
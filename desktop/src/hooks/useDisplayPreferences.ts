@@ -0,0 +1,26 @@
+import { useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+
+const DEFAULT_FONT_SIZE = 14
+
+// Apply the font size and locale preferences to the document root
+export function useDisplayPreferences() {
+  useEffect(() => {
+    const root = document.documentElement
+
+    invoke<{ font_size?: number }>('get_settings')
+      .then((settings) => {
+        const size = settings.font_size ?? DEFAULT_FONT_SIZE
+        // Scale relative to the default so 14pt keeps the stock layout; spacing and
+        // text are rem-based, so this scales the whole UI
+        root.style.fontSize = `${(size / DEFAULT_FONT_SIZE) * 100}%`
+      })
+      .catch(() => {})
+
+    invoke<string>('get_locale')
+      .then((locale) => {
+        root.lang = locale
+      })
+      .catch(() => {})
+  }, [])
+}
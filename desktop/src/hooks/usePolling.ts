@@ -1,9 +1,9 @@
 import { useEffect, useRef } from 'react'
 import { useQueryClient } from '@tanstack/react-query'
 import { invoke } from '@tauri-apps/api/core'
-import { isPermissionGranted, requestPermission, sendNotification } from '@tauri-apps/plugin-notification'
 import { apiGet } from '../api/client'
 import type { EmailListResponse } from '@relate/shared/api/types'
+import { ensureNotificationPermission } from '../lib/notifications'
 
 const POLL_INTERVAL = 300_000 // 5 minutes (SignalR is primary, polling is fallback)
 
@@ -31,15 +31,7 @@ export function usePolling(enabled = true) {
           previousUnreadRef.current !== null &&
           unreadCount > previousUnreadRef.current
         ) {
-          const newCount = unreadCount - previousUnreadRef.current
-          try {
-            const settings = await invoke<{ show_notifications: boolean }>('get_settings')
-            if (settings.show_notifications) {
-              await notifyNewEmails(newCount)
-            }
-          } catch {
-            await notifyNewEmails(newCount)
-          }
+          await notifyNewEmails(unreadCount - previousUnreadRef.current)
         }
 
         previousUnreadRef.current = unreadCount
@@ -65,14 +57,9 @@ export function usePolling(enabled = true) {
 
 async function notifyNewEmails(count: number) {
   try {
-    let permitted = await isPermissionGranted()
-    if (!permitted) {
-      const permission = await requestPermission()
-      permitted = permission === 'granted'
-    }
-
-    if (permitted) {
-      sendNotification({
+    // The backend applies the notification toggle, schedule, focus and sound settings
+    if (await ensureNotificationPermission()) {
+      await invoke('send_app_notification', {
         title: 'Relate Mail',
         body: count === 1
           ? 'You have 1 new email'
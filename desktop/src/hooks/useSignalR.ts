@@ -1,7 +1,6 @@
 import { useEffect, useRef, useState } from 'react'
 import { useQueryClient } from '@tanstack/react-query'
 import { invoke } from '@tauri-apps/api/core'
-import { isPermissionGranted, requestPermission, sendNotification } from '@tauri-apps/plugin-notification'
 import {
   connect,
   disconnect,
@@ -13,6 +12,7 @@ import {
   onReconnected,
   onClose,
 } from '../api/signalr'
+import { ensureNotificationPermission } from '../lib/notifications'
 
 export function useSignalR(serverUrl: string | null, apiKey: string | null) {
   const queryClient = useQueryClient()
@@ -104,21 +104,9 @@ export function useSignalR(serverUrl: string | null, apiKey: string | null) {
 
 async function notifyNewEmails() {
   try {
-    const settings = await invoke<{ show_notifications: boolean }>('get_settings')
-    if (!settings.show_notifications) return
-  } catch {
-    // If settings unavailable, still show notification
-  }
-
-  try {
-    let permitted = await isPermissionGranted()
-    if (!permitted) {
-      const permission = await requestPermission()
-      permitted = permission === 'granted'
-    }
-
-    if (permitted) {
-      sendNotification({
+    // The backend applies the notification toggle, schedule, focus and sound settings
+    if (await ensureNotificationPermission()) {
+      await invoke('send_app_notification', {
         title: 'Relate Mail',
         body: 'You have a new email',
       })
@@ -0,0 +1,20 @@
+import { invoke } from '@tauri-apps/api/core'
+
+type NotificationPermission = 'granted' | 'denied' | 'not_determined'
+
+/**
+ * Check notification permission, prompting only the first time. A previous denial is
+ * remembered in settings so the user isn't asked again on every launch.
+ */
+export async function ensureNotificationPermission(): Promise<boolean> {
+  const settings = await invoke<{ notification_permission_status: NotificationPermission | null }>(
+    'get_settings'
+  )
+  if (settings.notification_permission_status === 'denied') return false
+
+  let status = await invoke<NotificationPermission>('get_notification_permission_status')
+  if (status === 'not_determined') {
+    status = await invoke<NotificationPermission>('request_notification_permission')
+  }
+  return status === 'granted'
+}
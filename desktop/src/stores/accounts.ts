@@ -58,6 +58,10 @@ export const loadAccountsAtom = atom(null, async (_get, set) => {
       activeAccountId: data.active_account_id,
       loaded: true,
     })
+    if (data.active_account_id) {
+      // Establish the server connection early; failures surface on the first real request
+      invoke<number>('warm_connection').catch(() => {})
+    }
     return data
   } catch (err) {
     set(accountsStateAtom, (prev) => ({ ...prev, loaded: true }))
@@ -65,6 +69,15 @@ export const loadAccountsAtom = atom(null, async (_get, set) => {
   }
 })
 
+// Details of an account's API key, stored separately from the key itself
+export interface ApiKeyMetadata {
+  id: string
+  name: string
+  scopes: string[]
+  created_at: string
+  expires_at: string | null
+}
+
 // Action: Add a new account - atomic update
 export const addAccountAtom = atom(
   null,
@@ -74,14 +87,17 @@ export const addAccountAtom = atom(
     {
       account,
       apiKey,
+      apiKeyMetadata,
     }: {
       account: Account
       apiKey: string
+      apiKeyMetadata?: ApiKeyMetadata
     }
   ) => {
     const data = await invoke<AccountsData>('save_account', {
       account,
       apiKey,
+      apiKeyMetadata: apiKeyMetadata ?? null,
     })
     // Atomic update from Rust response
     set(accountsStateAtom, (prev) => ({
@@ -63,6 +63,30 @@ const stepMessages: Record<Exclude<Step, 'url'>, string> = {
   'creating-key': 'Setting up your account...',
 }
 
+// OIDC commands reject with { message, retriable }, account commands with
+// { message, user_message }; other commands reject with a string
+interface OidcError {
+  message: string
+  retriable: boolean
+}
+
+interface AuthError {
+  message: string
+  user_message: string
+}
+
+function errorMessage(err: unknown): string {
+  if (typeof err === 'string') return err
+  if (err instanceof Error) return err.message
+  if (err && typeof err === 'object' && 'user_message' in err) {
+    return (err as AuthError).user_message
+  }
+  if (err && typeof err === 'object' && 'message' in err) {
+    return (err as OidcError).message
+  }
+  return 'Connection failed'
+}
+
 interface LoginProps {
   onLoginComplete?: () => void
 }
@@ -185,14 +209,24 @@ export function Login({ onLoginComplete }: LoginProps) {
         last_used_at: now,
       }
 
-      await addAccount({ account, apiKey: apiKeyResp.apiKey })
+      await addAccount({
+        account,
+        apiKey: apiKeyResp.apiKey,
+        apiKeyMetadata: {
+          id: apiKeyResp.id,
+          name: apiKeyResp.name,
+          scopes: account.scopes,
+          created_at: apiKeyResp.createdAt,
+          expires_at: null,
+        },
+      })
 
       // Notify parent that login is complete
       if (onLoginComplete) {
         onLoginComplete()
       }
     } catch (err) {
-      setError(typeof err === 'string' ? err : err instanceof Error ? err.message : 'Connection failed')
+      setError(errorMessage(err))
       setStep('url')
     }
   }